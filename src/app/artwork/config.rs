@@ -0,0 +1,4 @@
+pub const CACHE_DIR: &str = "artwork";
+pub const CACHE_MAX_SIZE: u64 = 32 * 1024 * 1024;
+pub const CACHE_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+pub const PARTIAL_EXTENSION: &str = "part";
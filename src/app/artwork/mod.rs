@@ -0,0 +1,179 @@
+mod config;
+
+use std::{
+    cell::{Cell, RefCell},
+    fs::{self, File},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+use gtk::glib::{self, ChecksumType, clone};
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+use url::Url;
+use webkit::NetworkSession;
+
+use crate::config::DATA_DIR;
+
+use config::{CACHE_DIR, CACHE_MAX_FILE_SIZE, CACHE_MAX_SIZE, PARTIAL_EXTENSION};
+
+/// Artwork downloaded once and shared by the desktop integrations as local files.
+pub struct Artwork {
+    dir: Option<PathBuf>,
+    session: Option<NetworkSession>,
+    /// Keeps concurrent downloads of the same url from sharing a partial file.
+    next_download: Cell<u64>,
+}
+
+impl Artwork {
+    pub fn new(session: Option<NetworkSession>) -> Self {
+        let dir = dirs::data_dir().map(|dir| dir.join(DATA_DIR).join(CACHE_DIR));
+
+        Self {
+            dir,
+            session,
+            next_download: Cell::new(0),
+        }
+    }
+
+    /// Returns a `file://` url for the artwork, or the original url if it cannot be cached.
+    pub async fn resolve(&self, url: String) -> String {
+        match self.fetch(&url).await {
+            Ok(path) => Url::from_file_path(&path).map_or(url, String::from),
+            Err(e) => {
+                error!("Failed to cache artwork {url}: {e}");
+                url
+            }
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<PathBuf, String> {
+        let scheme = Url::parse(url)
+            .map_err(|e| e.to_string())?
+            .scheme()
+            .to_owned();
+        if scheme != "http" && scheme != "https" {
+            return Err(format!("Unsupported scheme {scheme}"));
+        }
+
+        let dir = self.dir.as_ref().ok_or("Failed to get data dir")?;
+        let key = cache_key(url).ok_or("Failed to hash url")?;
+        let path = dir.join(&key);
+
+        if path.exists() {
+            // Bumping the mtime keeps recently used artwork from being evicted
+            File::open(&path)
+                .and_then(|file| file.set_modified(SystemTime::now()))
+                .ok();
+
+            return Ok(path);
+        }
+
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache dir: {e}"))?;
+
+        let session = self.session.as_ref().ok_or("No network session")?;
+
+        // Downloads land in a partial file first, so readers never see a truncated image
+        let index = self
+            .next_download
+            .replace(self.next_download.get().wrapping_add(1));
+        let partial = dir.join(format!("{key}.{index}.{PARTIAL_EXTENSION}"));
+
+        download(session, url, &partial).await.inspect_err(|_| {
+            fs::remove_file(&partial).ok();
+        })?;
+
+        let size = fs::metadata(&partial).map_or(0, |metadata| metadata.len());
+        if size > CACHE_MAX_FILE_SIZE {
+            fs::remove_file(&partial).ok();
+            return Err(format!("Artwork is too large ({size} bytes)"));
+        }
+
+        fs::rename(&partial, &path).map_err(|e| {
+            fs::remove_file(&partial).ok();
+            format!("Failed to store artwork: {e}")
+        })?;
+
+        evict(dir, CACHE_MAX_SIZE);
+
+        Ok(path)
+    }
+}
+
+/// Sha256 of the url, stable across releases unlike the std hashers.
+fn cache_key(url: &str) -> Option<String> {
+    glib::compute_checksum_for_string(ChecksumType::Sha256, url).map(String::from)
+}
+
+async fn download(session: &NetworkSession, url: &str, path: &Path) -> Result<(), String> {
+    let (sender, receiver) = oneshot::channel();
+    let sender = Rc::new(RefCell::new(Some(sender)));
+
+    let download = session.download_uri(url);
+    download.set_allow_overwrite(true);
+
+    let destination = path.to_string_lossy().into_owned();
+    download.connect_decide_destination(move |download, _| {
+        download.set_destination(&destination);
+        true
+    });
+
+    // Finished is also emitted after a failure, so only the first result is kept
+    download.connect_failed(clone!(
+        #[strong]
+        sender,
+        move |_, e| {
+            if let Some(sender) = sender.borrow_mut().take() {
+                sender.send(Err(e.to_string())).ok();
+            }
+        }
+    ));
+
+    download.connect_finished(move |_| {
+        if let Some(sender) = sender.borrow_mut().take() {
+            sender.send(Ok(())).ok();
+        }
+    });
+
+    receiver
+        .await
+        .map_err(|_| "Download was dropped".to_owned())?
+}
+
+/// Removes the least recently used files until the cache fits in `max_size`.
+fn evict(dir: &Path, max_size: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files = entries
+        .flatten()
+        .filter_map(|entry| {
+            // Partial files belong to downloads in progress
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == PARTIAL_EXTENSION)
+            {
+                return None;
+            }
+
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            metadata.is_file().then(|| (modified, metadata.len(), path))
+        })
+        .collect::<Vec<_>>();
+
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut total = 0;
+    for (_, size, path) in files {
+        total += size;
+
+        if total > max_size {
+            debug!("Evicting cached artwork {}", path.display());
+            fs::remove_file(&path).ok();
+        }
+    }
+}
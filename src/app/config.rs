@@ -5,3 +5,25 @@ pub const APP_ID: &str = match cfg!(debug_assertions) {
 pub const APP_NAME: &str = "Stremio";
 pub const URI_SCHEME: &str = "stremio://";
 pub const STYLE: &str = include_str!("style.css");
+pub const LOADING_PAGE: &str = include_str!("pages/loading.html");
+pub const ERROR_PAGE: &str = include_str!("pages/error.html");
+pub const HWDEC_OPTIONS: &[&str] = &["auto", "auto-safe", "vaapi", "nvdec", "vulkan", "no"];
+pub const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt", "sub"];
+pub const MEDIA_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "m4v", "avi", "webm", "mov", "wmv", "flv", "ts", "m2ts", "mpg", "mpeg", "ogv",
+    "torrent",
+];
+pub const MAGNET_SCHEME: &str = "magnet:";
+pub const PLAYBACK_COMMANDS: &[(&str, &[&str])] = &[
+    ("play-pause", &["cycle", "pause"]),
+    ("seek-forward", &["seek", "5"]),
+    ("seek-backward", &["seek", "-5"]),
+    ("seek-forward-long", &["seek", "30"]),
+    ("seek-backward-long", &["seek", "-30"]),
+    ("volume-up", &["add", "volume", "5"]),
+    ("volume-down", &["add", "volume", "-5"]),
+    ("mute", &["cycle", "mute"]),
+    ("next-subtitle", &["cycle", "sub"]),
+    ("next-audio", &["cycle", "audio"]),
+];
+pub const SERVER_LOG_MAX_SIZE: u64 = 256 * 1024;
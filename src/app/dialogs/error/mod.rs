@@ -0,0 +1,25 @@
+use adw::prelude::{AdwDialogExt, AlertDialogExt};
+use gettextrs::gettext;
+use gtk::glib::object::IsA;
+
+pub struct ErrorDialog {
+    dialog: adw::AlertDialog,
+}
+
+impl ErrorDialog {
+    pub fn new(heading: &str, body: &str) -> Self {
+        let dialog = adw::AlertDialog::builder()
+            .heading(heading)
+            .body(body)
+            .build();
+
+        dialog.add_response("close", &gettext("_Close"));
+        dialog.set_default_response(Some("close"));
+
+        Self { dialog }
+    }
+
+    pub fn show(&self, parent: &impl IsA<gtk::Widget>) {
+        self.dialog.present(Some(parent));
+    }
+}
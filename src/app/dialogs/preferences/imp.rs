@@ -1,11 +1,23 @@
-use adw::subclass::prelude::*;
+use adw::{
+    prelude::{ActionRowExt, ComboRowExt, PreferencesGroupExt},
+    subclass::prelude::*,
+};
+use gettextrs::gettext;
 use gtk::{
+    gdk::prelude::DisplayExt,
     gio::{Settings, prelude::SettingsExt},
     glib::{self, subclass::InitializingObject},
-    prelude::WidgetExt,
+    prelude::{TextBufferExt, TextViewExt, WidgetExt},
 };
 
-use crate::{app::config::APP_ID, utils::IS_DESKTOP_KDE};
+use crate::{
+    app::{
+        config::{APP_ID, HWDEC_OPTIONS, SERVER_LOG_MAX_SIZE},
+        video,
+    },
+    server,
+    utils::IS_DESKTOP_KDE,
+};
 
 #[derive(Default, gtk::CompositeTemplate)]
 #[template(file = "preferences.xml")]
@@ -14,6 +26,21 @@ pub struct PreferencesDialog {
     remember_window_state: TemplateChild<adw::SwitchRow>,
     #[template_child]
     kde_theme: TemplateChild<adw::SwitchRow>,
+    #[template_child]
+    hwdec: TemplateChild<adw::ComboRow>,
+    #[template_child]
+    mpv_user_config: TemplateChild<adw::SwitchRow>,
+    #[template_child]
+    server_logs_group: TemplateChild<adw::PreferencesGroup>,
+    #[template_child]
+    server_logs: TemplateChild<gtk::TextView>,
+}
+
+impl PreferencesDialog {
+    fn load_server_logs(&self) {
+        let logs = server::read_log(SERVER_LOG_MAX_SIZE);
+        self.server_logs.buffer().set_text(&logs);
+    }
 }
 
 #[gtk::template_callbacks]
@@ -32,6 +59,36 @@ impl PreferencesDialog {
             .set_boolean("kde-theme", self.kde_theme.is_active())
             .ok();
     }
+
+    #[template_callback]
+    fn on_hwdec_changed(&self) {
+        let settings = Settings::new(APP_ID);
+        if let Some(hwdec) = HWDEC_OPTIONS.get(self.hwdec.selected() as usize) {
+            settings.set_string("hwdec", hwdec).ok();
+        }
+    }
+
+    #[template_callback]
+    fn on_mpv_user_config_changed(&self) {
+        let settings = Settings::new(APP_ID);
+        settings
+            .set_boolean("mpv-user-config", self.mpv_user_config.is_active())
+            .ok();
+    }
+
+    #[template_callback]
+    fn on_server_logs_refresh(&self) {
+        self.load_server_logs();
+    }
+
+    #[template_callback]
+    fn on_server_logs_copy(&self) {
+        let buffer = self.server_logs.buffer();
+        let (start, end) = buffer.bounds();
+        let logs = buffer.text(&start, &end, false);
+
+        self.obj().display().clipboard().set_text(&logs);
+    }
 }
 
 #[glib::object_subclass]
@@ -65,6 +122,27 @@ impl ObjectImpl for PreferencesDialog {
 
         let remember_window_state = settings.boolean("remember-window-state");
         self.remember_window_state.set_active(remember_window_state);
+
+        let hwdec = settings.string("hwdec");
+        if let Some(position) = HWDEC_OPTIONS.iter().position(|option| *option == hwdec) {
+            self.hwdec.set_selected(position as u32);
+        }
+
+        let mpv_user_config = settings.boolean("mpv-user-config");
+        self.mpv_user_config.set_active(mpv_user_config);
+
+        let mpv_config_dir = video::user_config_dir().display().to_string();
+        self.mpv_user_config.set_subtitle(
+            &gettext("Use mpv.conf, input.conf and scripts from {}, startup only options are ignored, requires restarting the application")
+                .replace("{}", &mpv_config_dir),
+        );
+
+        if let Some(path) = server::log_path() {
+            let path = path.display().to_string();
+            self.server_logs_group.set_description(Some(&path));
+        }
+
+        self.load_server_logs();
     }
 }
 
@@ -1 +1,11 @@
+use std::time::Duration;
+
 pub const CLIENT_ID: &str = "1452620752263319665";
+pub const IPC_SOCKET_NAME: &str = "discord-ipc";
+pub const IPC_SOCKET_COUNT: u8 = 10;
+pub const IPC_SOCKET_DIRS: &[&str] = &["", "app/com.discordapp.Discord", "snap.discord"];
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(15);
+pub const PAUSED_IMAGE: &str = "paused";
+pub const MAX_BUTTONS: usize = 2;
+/// Drift in seconds tolerated before timestamps are sent again.
+pub const TIMESTAMP_TOLERANCE: i64 = 2;
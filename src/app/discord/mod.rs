@@ -1,62 +1,417 @@
 mod config;
 
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    env,
+    path::PathBuf,
+    rc::{Rc, Weak},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use discord_rich_presence::{
     DiscordIpc, DiscordIpcClient,
-    activity::{Activity, ActivityType, Assets},
+    activity::{Activity, ActivityType, Assets, Button, Timestamps},
+};
+use flume::{Receiver, Sender};
+use gtk::glib;
+use tokio::sync::oneshot;
+use tracing::{error, info};
+
+use crate::spawn_local;
+
+use config::{
+    CLIENT_ID, IPC_SOCKET_COUNT, IPC_SOCKET_DIRS, IPC_SOCKET_NAME, MAX_BUTTONS, PAUSED_IMAGE,
+    RECONNECT_INTERVAL, TIMESTAMP_TOLERANCE,
 };
-use tracing::error;
 
-use config::CLIENT_ID;
+type StatusCallback = Box<dyn Fn(bool)>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiscordActivity {
+    pub details: String,
+    pub state: String,
+    pub image: Option<String>,
+    /// Label and url pairs, Discord shows at most two.
+    pub buttons: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiscordPlayback {
+    pub position: f64,
+    pub duration: f64,
+    pub paused: bool,
+}
+
+/// Everything needed to build an activity payload on the worker thread.
+#[derive(Debug)]
+struct Presence {
+    activity: DiscordActivity,
+    /// Start and optional end timestamps, only set while playing.
+    timestamps: Option<(i64, Option<i64>)>,
+    paused: bool,
+}
+
+#[derive(Debug)]
+enum Request {
+    Connect,
+    Close,
+    SetActivity(Presence),
+    ClearActivity,
+}
+
+#[derive(Debug)]
+enum Reply {
+    Connected(bool),
+    ConnectionLost,
+}
 
 pub struct Discord {
-    client: RefCell<DiscordIpcClient>,
+    /// Discord ipc blocks on socket io, so the client lives on its own thread.
+    requests: Sender<Request>,
+    /// Whether the UI asked for a connection, which the reconnect loop restores.
+    enabled: Cell<bool>,
+    connected: Cell<bool>,
+    connecting: Cell<bool>,
+    reconnecting: Cell<bool>,
+    /// Callers of `connect` waiting for the worker to report the outcome.
+    pending: RefCell<Vec<oneshot::Sender<bool>>>,
+    activity: RefCell<Option<DiscordActivity>>,
+    playback: Cell<Option<DiscordPlayback>>,
+    /// Start and end timestamps and paused state of the last activity sent.
+    sent: Cell<Option<(Option<i64>, Option<i64>, bool)>>,
+    status_callback: RefCell<Option<StatusCallback>>,
 }
 
 impl Discord {
-    pub fn new() -> Self {
-        let client = RefCell::new(DiscordIpcClient::new(CLIENT_ID));
+    pub fn new() -> Rc<Self> {
+        let (requests, request_receiver) = flume::unbounded();
+        let (reply_sender, replies) = flume::unbounded();
+
+        thread::Builder::new()
+            .name("discord".to_owned())
+            .spawn(move || run_client(request_receiver, reply_sender))
+            .expect("Failed to spawn discord thread");
+
+        let discord = Rc::new(Self {
+            requests,
+            enabled: Cell::new(false),
+            connected: Cell::new(false),
+            connecting: Cell::new(false),
+            reconnecting: Cell::new(false),
+            pending: RefCell::new(Vec::new()),
+            activity: RefCell::new(None),
+            playback: Cell::new(None),
+            sent: Cell::new(None),
+            status_callback: RefCell::new(None),
+        });
+
+        let weak = Rc::downgrade(&discord);
+        spawn_local!(async move {
+            while let Ok(reply) = replies.recv_async().await {
+                let Some(discord) = weak.upgrade() else {
+                    break;
+                };
+
+                discord.on_reply(reply);
+            }
+        });
 
-        Self { client }
+        discord
     }
 
-    pub fn connect(&self) -> bool {
-        if let Err(e) = self.client.borrow_mut().connect() {
-            error!("Failed to connect: {e}");
-            return false;
+    /// Whether a Discord client is running and listening on one of its ipc sockets.
+    pub fn is_available(&self) -> bool {
+        let runtime_dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+            .into_iter()
+            .find_map(env::var_os)
+            .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from);
+
+        IPC_SOCKET_DIRS.iter().any(|dir| {
+            (0..IPC_SOCKET_COUNT).any(|index| {
+                runtime_dir
+                    .join(dir)
+                    .join(format!("{IPC_SOCKET_NAME}-{index}"))
+                    .exists()
+            })
+        })
+    }
+
+    /// Resolves to whether the connection succeeded, failures keep retrying in the background.
+    pub fn connect(&self) -> impl Future<Output = bool> + 'static {
+        self.enabled.set(true);
+
+        let (sender, receiver) = oneshot::channel();
+        if self.connected.get() {
+            sender.send(true).ok();
+        } else {
+            self.pending.borrow_mut().push(sender);
+            self.request_connect();
         }
 
-        true
+        async move { receiver.await.unwrap_or(false) }
     }
 
     pub fn disconnect(&self) {
-        if let Err(e) = self.client.borrow_mut().close() {
-            error!("Failed to disconnect: {e}");
+        self.enabled.set(false);
+        self.sent.set(None);
+        self.send(Request::Close);
+        self.set_connected(false);
+    }
+
+    pub fn set_activity(&self, activity: DiscordActivity) {
+        if self.activity.borrow().as_ref() == Some(&activity) {
+            return;
         }
+
+        self.activity.replace(Some(activity));
+        self.sent.set(None);
+        self.update_activity();
     }
 
-    pub fn set_activity(&self, details: String, state: String, image: Option<String>) {
-        let mut assets = Assets::new().large_text("Stremio");
+    pub fn clear_activity(&self) {
+        if self.activity.take().is_none() {
+            return;
+        }
+
+        self.sent.set(None);
+
+        if self.connected.get() {
+            self.send(Request::ClearActivity);
+        }
+    }
 
-        if let Some(image) = image {
-            assets = assets.large_image(image);
+    /// Updates the timestamps and paused state, `None` when nothing is playing.
+    pub fn set_playback(&self, playback: Option<DiscordPlayback>) {
+        if self.playback.replace(playback) != playback {
+            self.update_activity();
         }
+    }
 
-        let activity = Activity::default()
-            .activity_type(ActivityType::Watching)
-            .details(details)
-            .state(state)
-            .assets(assets);
+    pub fn connect_status<F: Fn(bool) + 'static>(&self, callback: F) {
+        self.status_callback.replace(Some(Box::new(callback)));
+    }
 
-        if let Err(e) = self.client.borrow_mut().set_activity(activity) {
-            error!("Failed to set activity: {e}");
+    fn send(&self, request: Request) {
+        if self.requests.send(request).is_err() {
+            error!("Discord thread is gone");
         }
     }
 
-    pub fn clear_activity(&self) {
-        if let Err(e) = self.client.borrow_mut().clear_activity() {
-            error!("Failed to clear activity: {e}");
+    fn request_connect(&self) {
+        if !self.connecting.replace(true) {
+            self.send(Request::Connect);
+        }
+    }
+
+    fn on_reply(self: &Rc<Self>, reply: Reply) {
+        match reply {
+            Reply::Connected(connected) => {
+                self.connecting.set(false);
+
+                // The UI may have disconnected while the attempt was running
+                let connected = connected && self.enabled.get();
+                if !connected && self.enabled.get() {
+                    self.schedule_reconnect();
+                }
+
+                for sender in self.pending.take() {
+                    sender.send(connected).ok();
+                }
+
+                if connected {
+                    info!("Connected to Discord");
+                    self.set_connected(true);
+                    self.update_activity();
+                } else {
+                    self.send(Request::Close);
+                    self.set_connected(false);
+                }
+            }
+            Reply::ConnectionLost => self.connection_lost(),
+        }
+    }
+
+    fn update_activity(&self) {
+        if !self.connected.get() {
+            return;
+        }
+
+        let Some(activity) = self.activity.borrow().clone() else {
+            return;
+        };
+
+        let playback = self.playback.get();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64);
+
+        let start = playback.map(|playback| now - playback.position as i64);
+        let end = start
+            .zip(playback)
+            .filter(|(_, playback)| playback.duration > 0.0)
+            .map(|(start, playback)| start + playback.duration as i64);
+        let paused = playback.is_some_and(|playback| playback.paused);
+
+        // Position changes every frame, only resend when the timestamps drift,
+        // a paused activity has no timestamps so only the paused state matters
+        if let Some((sent_start, sent_end, sent_paused)) = self.sent.get()
+            && sent_paused == paused
+            && (paused || (is_close(start, sent_start) && is_close(end, sent_end)))
+        {
+            return;
         }
+
+        let timestamps = start.filter(|_| !paused).map(|start| (start, end));
+
+        self.send(Request::SetActivity(Presence {
+            activity,
+            timestamps,
+            paused,
+        }));
+
+        self.sent.set(Some((start, end, paused)));
+    }
+
+    fn set_connected(&self, connected: bool) {
+        if self.connected.replace(connected) != connected {
+            self.notify_status(connected);
+        }
+    }
+
+    fn notify_status(&self, connected: bool) {
+        if let Some(ref callback) = *self.status_callback.borrow() {
+            callback(connected);
+        }
+    }
+
+    fn connection_lost(self: &Rc<Self>) {
+        self.sent.set(None);
+        self.set_connected(false);
+        self.schedule_reconnect();
+    }
+
+    fn schedule_reconnect(self: &Rc<Self>) {
+        if self.reconnecting.replace(true) {
+            return;
+        }
+
+        let discord = Rc::downgrade(self);
+        glib::timeout_add_local_once(RECONNECT_INTERVAL, move || {
+            Self::reconnect(discord);
+        });
+    }
+
+    fn reconnect(discord: Weak<Self>) {
+        let Some(discord) = discord.upgrade() else {
+            return;
+        };
+
+        discord.reconnecting.set(false);
+
+        if !discord.enabled.get() || discord.connected.get() {
+            return;
+        }
+
+        if discord.is_available() {
+            discord.request_connect();
+        } else {
+            discord.schedule_reconnect();
+        }
+    }
+}
+
+/// Whether two timestamps are equal within the tolerance, a missing one only matches another.
+fn is_close(timestamp: Option<i64>, sent: Option<i64>) -> bool {
+    match (timestamp, sent) {
+        (Some(timestamp), Some(sent)) => (timestamp - sent).abs() <= TIMESTAMP_TOLERANCE,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Owns the ipc client, runs until the `Discord` handle is dropped.
+fn run_client(requests: Receiver<Request>, replies: Sender<Reply>) {
+    let mut client = DiscordIpcClient::new(CLIENT_ID);
+
+    for request in requests.iter() {
+        let reply = match request {
+            Request::Connect => {
+                let connected = client
+                    .connect()
+                    .inspect_err(|e| error!("Failed to connect: {e}"))
+                    .is_ok();
+
+                Reply::Connected(connected)
+            }
+            Request::Close => {
+                if let Err(e) = client.close() {
+                    error!("Failed to disconnect: {e}");
+                }
+
+                continue;
+            }
+            Request::SetActivity(presence) => match client.set_activity(payload(presence)) {
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("Failed to set activity: {e}");
+                    client.close().ok();
+                    Reply::ConnectionLost
+                }
+            },
+            Request::ClearActivity => match client.clear_activity() {
+                Ok(_) => continue,
+                Err(e) => {
+                    error!("Failed to clear activity: {e}");
+                    client.close().ok();
+                    Reply::ConnectionLost
+                }
+            },
+        };
+
+        if replies.send(reply).is_err() {
+            break;
+        }
+    }
+}
+
+fn payload(presence: Presence) -> Activity {
+    let activity = presence.activity;
+    let mut assets = Assets::new().large_text("Stremio");
+
+    if let Some(image) = activity.image {
+        assets = assets.large_image(image);
+    }
+
+    if presence.paused {
+        assets = assets.small_image(PAUSED_IMAGE).small_text("Paused");
+    }
+
+    let mut payload = Activity::default()
+        .activity_type(ActivityType::Watching)
+        .details(activity.details)
+        .state(activity.state)
+        .assets(assets);
+
+    if let Some((start, end)) = presence.timestamps {
+        let mut timestamps = Timestamps::new().start(start);
+        if let Some(end) = end {
+            timestamps = timestamps.end(end);
+        }
+
+        payload = payload.timestamps(timestamps);
+    }
+
+    let buttons = activity
+        .buttons
+        .into_iter()
+        .take(MAX_BUTTONS)
+        .map(|(label, url)| Button::new(label, url))
+        .collect::<Vec<_>>();
+
+    if !buttons.is_empty() {
+        payload = payload.buttons(buttons);
     }
+
+    payload
 }
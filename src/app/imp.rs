@@ -1,24 +1,51 @@
-use std::cell::{Cell, RefCell};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future,
+    rc::Rc,
+};
 
 use adw::{prelude::*, subclass::prelude::*};
-use gtk::glib::{self, Properties, clone};
-use tracing::error;
+use gettextrs::gettext;
+use gtk::{
+    gio::{Settings, SimpleAction, prelude::SettingsExt},
+    glib::{self, Properties, clone},
+};
+use itertools::Itertools;
+use serde::de::IgnoredAny;
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+use url::Url;
 
 use crate::{
     app::{
-        config::{APP_ID, APP_NAME, URI_SCHEME},
-        discord::Discord,
+        artwork::Artwork,
+        config::{
+            APP_ID, APP_NAME, ERROR_PAGE, LOADING_PAGE, PLAYBACK_COMMANDS, SUBTITLE_EXTENSIONS,
+            URI_SCHEME,
+        },
+        dialogs::error::ErrorDialog,
+        discord::{Discord, DiscordActivity, DiscordPlayback},
         ipc::{
-            self,
-            event::{IpcEvent, IpcEventDiscord, IpcEventMpv},
+            self, IpcCapabilities, IpcCapabilitiesMpv, IpcError, IpcErrorCode, IpcFuture,
+            IpcRegistry, IpcSchema,
+            event::{IpcEvent, IpcEventDiscord, IpcEventMpv, IpcEventServer},
+            request::{
+                IpcCall, IpcMessageRequestDiscordSetActivity, IpcMessageRequestMediaMetadata,
+                IpcMessageRequestMediaStatus, IpcMessageRequestScreenshot,
+                IpcMessageRequestWinSetVisilibty,
+            },
         },
-        mpris::Mpris,
+        mpris::{MediaMetadata, Mpris},
+        pip::PipWindow,
         tray::Tray,
-        video::Video,
+        video::{PropertyError, ScreenshotFormat, Video},
         webview::WebView,
         window::Window,
     },
-    spawn_local, utils,
+    server::{self, Server, ServerEvent},
+    spawn_local,
+    utils::{self, uri_extension},
 };
 
 const PRELOAD_SCRIPT: &str = include_str!("ipc/preload.js");
@@ -32,11 +59,702 @@ pub struct Application {
     startup_url: RefCell<String>,
     #[property(get, set)]
     decorations: Cell<bool>,
+    #[property(get, set)]
+    server_url: RefCell<String>,
+    #[property(get, set)]
+    safe_mode: Cell<bool>,
     tray: RefCell<Option<Tray>>,
     mpris: RefCell<Option<Mpris>>,
     window: RefCell<Option<Window>>,
     webview: RefCell<Option<WebView>>,
+    video: RefCell<Option<Video>>,
+    discord: RefCell<Option<Rc<Discord>>>,
+    pip: RefCell<Option<PipWindow>>,
+    /// Kept alive so the playback accels follow changes to the setting.
+    settings: RefCell<Option<Settings>>,
     deeplink: RefCell<Option<String>>,
+    pub server: RefCell<Option<Server>>,
+}
+
+impl Application {
+    fn load_startup_url(&self) {
+        let Some(webview) = self.webview.borrow().clone() else {
+            return;
+        };
+
+        let startup_url = self.startup_url.borrow().clone();
+        let server_url = self.server_url.borrow().clone();
+
+        if let Some(ref server) = *self.server.borrow()
+            && !server.is_running()
+            && let Err(e) = server.start()
+        {
+            error!("Failed to start server: {e:#}");
+            webview.load_html(&error_page(&gettext(
+                "The streaming server could not be started. Check the logs for details and try again.",
+            )));
+
+            if let Some(ref window) = *self.window.borrow() {
+                let dialog = ErrorDialog::new(
+                    &gettext("Failed to start streaming server"),
+                    &format!("{e:#}"),
+                );
+                dialog.show(window);
+            }
+
+            return;
+        }
+
+        webview.load_html(LOADING_PAGE);
+
+        // Without a server of our own, the url points to an external one
+        let external = self.server.borrow().is_none();
+
+        spawn_local!(clone!(
+            #[weak]
+            webview,
+            async move {
+                if server::wait_until_ready(&server_url).await {
+                    webview.load_uri(&startup_url);
+                    return;
+                }
+
+                let message = if external {
+                    error!("External server at {server_url} is not reachable");
+                    gettext("The streaming server at {} could not be reached. Make sure it is running and try again.")
+                        .replace("{}", &server_url)
+                } else {
+                    error!("Server is not ready after timeout");
+                    gettext(
+                        "The streaming server did not start in time. Check the logs for details and try again.",
+                    )
+                };
+
+                webview.load_html(&error_page(&message));
+            }
+        ));
+    }
+
+    fn capabilities(&self) -> IpcCapabilities {
+        let video = self.video.borrow();
+
+        IpcCapabilities {
+            tray: self.tray.borrow().as_ref().is_some_and(Tray::is_available),
+            mpris: self
+                .mpris
+                .borrow()
+                .as_ref()
+                .is_some_and(Mpris::is_available),
+            discord: self
+                .discord
+                .borrow()
+                .as_ref()
+                .is_some_and(|discord| discord.is_available()),
+            idle_inhibit: self
+                .window
+                .borrow()
+                .as_ref()
+                .is_some_and(Window::can_inhibit),
+            mpv: IpcCapabilitiesMpv {
+                version: video.as_ref().and_then(Video::mpv_version),
+                hwdec_interop: video.as_ref().and_then(Video::hwdec_interop),
+                hwdec_current: video.as_ref().and_then(Video::hwdec_current),
+            },
+        }
+    }
+
+    /// Probes finish after startup, so the web UI is told when their results change.
+    fn send_capabilities(&self) {
+        if let Some(ref webview) = *self.webview.borrow() {
+            let message = ipc::create_response(IpcEvent::Capabilities(self.capabilities()));
+            webview.send(&message);
+        }
+    }
+
+    /// Moves the video into a small window and hides the main one.
+    pub fn enter_pip(&self) {
+        if self.pip.borrow().is_some() {
+            return;
+        }
+
+        let (Some(window), Some(video)) =
+            (self.window.borrow().clone(), self.video.borrow().clone())
+        else {
+            return;
+        };
+
+        let app = self.obj();
+        let pip = PipWindow::new(&app);
+
+        window.clear_underlay();
+        pip.set_video(&video);
+        pip.set_paused(video.paused());
+
+        pip.connect_play_pause(clone!(
+            #[weak]
+            video,
+            move || {
+                video.toggle_pause();
+            }
+        ));
+
+        pip.connect_leave(clone!(
+            #[weak]
+            app,
+            move || {
+                app.imp().leave_pip();
+            }
+        ));
+
+        pip.present();
+        window.set_visible(false);
+
+        *self.pip.borrow_mut() = Some(pip);
+        self.send_pip_status(true);
+    }
+
+    /// Moves the video back into the main window.
+    pub fn leave_pip(&self) {
+        let Some(pip) = self.pip.take() else {
+            return;
+        };
+
+        pip.clear_video();
+        pip.destroy();
+
+        if let (Some(window), Some(video)) =
+            (self.window.borrow().as_ref(), self.video.borrow().as_ref())
+        {
+            window.set_underlay(video);
+            window.present();
+        }
+
+        self.send_pip_status(false);
+    }
+
+    fn send_pip_status(&self, active: bool) {
+        if let Some(ref webview) = *self.webview.borrow() {
+            let message = ipc::create_response(IpcEvent::Pip(active));
+            webview.send(&message);
+        }
+    }
+
+    /// Hands a deeplink, magnet or media file over to the web UI.
+    fn open_media(&self, uri: String) {
+        *self.deeplink.borrow_mut() = Some(uri.clone());
+
+        if let Some(ref webview) = *self.webview.borrow() {
+            let message = ipc::create_response(IpcEvent::OpenMedia(uri));
+            webview.send(&message);
+        }
+    }
+
+    /// Subtitles dropped during playback are added to the player, media is only opened when idle.
+    fn open_dropped(&self, uris: Vec<String>) {
+        let Some(video) = self.video.borrow().clone() else {
+            return;
+        };
+
+        let (subtitles, media): (Vec<_>, Vec<_>) = uris.into_iter().partition(|uri| {
+            uri_extension(uri)
+                .is_some_and(|extension| SUBTITLE_EXTENSIONS.contains(&extension.as_str()))
+        });
+
+        if !video.playing() {
+            if let Some(uri) = media.into_iter().next() {
+                self.open_media(uri);
+            }
+
+            return;
+        }
+
+        if !media.is_empty() {
+            info!("Ignoring dropped media during playback");
+        }
+
+        for uri in subtitles {
+            // mpv logs and reports local subtitles by path, so file uris are converted back
+            let path = Url::parse(&uri)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .map_or(uri, |path| path.to_string_lossy().into_owned());
+
+            let reply = video.add_subtitle(&path);
+            let webview = self.webview.borrow().clone();
+
+            spawn_local!(async move {
+                match reply.await {
+                    Ok(_) => {
+                        info!("Added subtitle {path}");
+
+                        if let Some(webview) = webview {
+                            let event = IpcEventMpv::Event((
+                                "sub-added".to_owned(),
+                                json!({ "path": path }),
+                            ));
+                            let message = ipc::create_response(IpcEvent::Mpv(event));
+                            webview.send(&message);
+                        }
+                    }
+                    Err(e) => error!("Failed to add subtitle {path}: {e}"),
+                }
+            });
+        }
+    }
+
+    pub fn take_screenshot(&self) {
+        let (Some(video), Some(webview)) =
+            (self.video.borrow().clone(), self.webview.borrow().clone())
+        else {
+            return;
+        };
+
+        let screenshot = video.screenshot(ScreenshotFormat::default(), true);
+        spawn_local!(async move {
+            match screenshot.await {
+                Ok(path) => {
+                    info!("Saved screenshot to {}", path.display());
+
+                    let path = path.display().to_string();
+                    let message = ipc::create_response(IpcEvent::Screenshot(path));
+                    webview.send(&message);
+                }
+                Err(e) => error!("Failed to take screenshot: {e}"),
+            }
+        });
+    }
+
+    /// Playback shortcuts handled by the shell, so they keep working without the webview focused.
+    fn setup_playback_actions(&self, window: &Window, webview: &WebView, video: &Video) {
+        let app = self.obj();
+
+        let mut actions = PLAYBACK_COMMANDS
+            .iter()
+            .map(|(name, command)| {
+                let action = SimpleAction::new(name, None);
+                action.connect_activate(clone!(
+                    #[weak]
+                    video,
+                    move |_, _| {
+                        video.run_command(command);
+                    }
+                ));
+
+                action
+            })
+            .collect_vec();
+
+        let fullscreen_action = SimpleAction::new("toggle-fullscreen", None);
+        fullscreen_action.connect_activate(clone!(
+            #[weak]
+            window,
+            #[weak]
+            webview,
+            move |_, _| {
+                let fullscreen = !window.is_fullscreen();
+                window.set_fullscreen(fullscreen);
+
+                let message = ipc::create_response(IpcEvent::Fullscreen(fullscreen));
+                webview.send(&message);
+            }
+        ));
+        actions.push(fullscreen_action);
+
+        for action in actions {
+            video
+                .bind_property("playing", &action, "enabled")
+                .sync_create()
+                .build();
+
+            app.add_action(&action);
+        }
+
+        let settings = Settings::new(APP_ID);
+        self.apply_playback_accels(&settings);
+
+        settings.connect_changed(
+            Some("playback-accels"),
+            clone!(
+                #[weak]
+                app,
+                move |settings, _| {
+                    app.imp().apply_playback_accels(settings);
+                }
+            ),
+        );
+
+        *self.settings.borrow_mut() = Some(settings);
+    }
+
+    /// Binds the configured accels, actions missing from the setting lose theirs.
+    fn apply_playback_accels(&self, settings: &Settings) {
+        let app = self.obj();
+        let accels = settings
+            .value("playback-accels")
+            .get::<HashMap<String, String>>()
+            .unwrap_or_default();
+
+        let names = PLAYBACK_COMMANDS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(["toggle-fullscreen"]);
+
+        for name in names {
+            let accel = accels.get(name).map(String::as_str);
+            app.set_accels_for_action(&format!("app.{name}"), accel.as_slice());
+        }
+    }
+
+    fn create_ipc_registry(
+        &self,
+        window: &Window,
+        webview: &WebView,
+        video: &Video,
+        mpris: &Mpris,
+        discord: &Rc<Discord>,
+        artwork: &Rc<Artwork>,
+    ) -> IpcRegistry {
+        let app = self.obj();
+        let mut registry = IpcRegistry::default();
+
+        let deeplink = self.deeplink.clone();
+        registry.register(
+            "app-ready",
+            clone!(
+                #[weak]
+                webview,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |_: IgnoredAny| {
+                    if let Some(ref uri) = *deeplink.borrow() {
+                        let message = ipc::create_response(IpcEvent::OpenMedia(uri.to_string()));
+                        webview.send(&message);
+                    }
+
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "quit",
+            clone!(
+                #[weak]
+                app,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |_: IgnoredAny| {
+                    app.quit();
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "pip-enter",
+            clone!(
+                #[weak]
+                app,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |_: IgnoredAny| {
+                    app.imp().enter_pip();
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "pip-leave",
+            clone!(
+                #[weak]
+                app,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |_: IgnoredAny| {
+                    app.imp().leave_pip();
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "server-retry",
+            clone!(
+                #[weak]
+                app,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |_: IgnoredAny| {
+                    app.imp().load_startup_url();
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "win-set-visibility",
+            clone!(
+                #[weak]
+                window,
+                #[weak]
+                webview,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |args: IpcMessageRequestWinSetVisilibty| {
+                    window.set_fullscreen(args.fullscreen);
+
+                    let message = ipc::create_response(IpcEvent::Fullscreen(args.fullscreen));
+                    webview.send(&message);
+
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        let weak_video = video.downgrade();
+        registry
+            .register_async("mpv-command", move |command: Value| {
+                let valid = match command {
+                    Value::Array(ref args) => !args.is_empty(),
+                    Value::Object(_) => true,
+                    _ => false,
+                };
+
+                let video = weak_video.upgrade();
+                let allowed = video
+                    .as_ref()
+                    .is_none_or(|video| video.is_mpv_command_allowed(&command));
+
+                let reply = video
+                    .filter(|_| valid && allowed)
+                    .map(|video| video.send_mpv_command(command));
+
+                async move {
+                    if !valid {
+                        return Err(IpcError::new(
+                            IpcErrorCode::InvalidArguments,
+                            "Invalid mpv-command arguments",
+                        ));
+                    }
+
+                    if !allowed {
+                        return Err(IpcError::new(
+                            IpcErrorCode::NotAllowed,
+                            "Command is not allowed",
+                        ));
+                    }
+
+                    match reply {
+                        Some(reply) => reply.await.map_err(IpcError::failed),
+                        None => Ok(Value::Null),
+                    }
+                }
+            })
+            .returns(Value::schema());
+
+        let weak_video = video.downgrade();
+        registry
+            .register_async(
+                "screenshot",
+                move |args: Option<IpcMessageRequestScreenshot>| {
+                    let args = args.unwrap_or_default();
+                    let screenshot = weak_video
+                        .upgrade()
+                        .map(|video| video.screenshot(args.format, args.subtitles));
+
+                    async move {
+                        match screenshot {
+                            Some(screenshot) => screenshot
+                                .await
+                                .map(|path| json!({ "path": path }))
+                                .map_err(IpcError::failed),
+                            None => Ok(Value::Null),
+                        }
+                    }
+                },
+            )
+            .returns(ipc::object(&[("path", String::schema())], &["path"]));
+
+        registry.register(
+            "mpv-observe-prop",
+            clone!(
+                #[weak]
+                video,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |name: String| {
+                    video
+                        .observe_mpv_property(&name)
+                        .map(|_| Value::Null)
+                        .map_err(|e| property_error(&name, e))
+                }
+            ),
+        );
+
+        registry.register(
+            "mpv-set-prop",
+            clone!(
+                #[weak]
+                video,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |(name, value): (String, Value)| {
+                    video
+                        .set_mpv_property(&name, &value)
+                        .map(|_| Value::Null)
+                        .map_err(|e| property_error(&name, e))
+                }
+            ),
+        );
+
+        let metadata_generation = Rc::new(Cell::new(0u64));
+        registry.register(
+            "media.metadata",
+            clone!(
+                #[weak]
+                mpris,
+                #[strong]
+                artwork,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |metadata: IpcMessageRequestMediaMetadata| {
+                    // Artwork is resolved asynchronously, so stale metadata must not win
+                    let generation = metadata_generation.get() + 1;
+                    metadata_generation.set(generation);
+
+                    let metadata_generation = metadata_generation.clone();
+                    let artwork = artwork.clone();
+
+                    spawn_local!(async move {
+                        let art_url = match metadata.art_url {
+                            Some(url) => Some(artwork.resolve(url).await),
+                            None => None,
+                        };
+
+                        if metadata_generation.get() != generation {
+                            return;
+                        }
+
+                        mpris.set_metadata(MediaMetadata {
+                            id: metadata.id,
+                            title: metadata.title,
+                            artist: metadata.artist,
+                            art_url,
+                            series: metadata.series,
+                            episode: metadata.episode,
+                            url: metadata.url,
+                            duration: metadata.duration,
+                        });
+                    });
+
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "media.status",
+            clone!(
+                #[weak]
+                window,
+                #[upgrade_or]
+                Ok(Value::Null),
+                move |status: IpcMessageRequestMediaStatus| {
+                    if status.paused {
+                        window.enable_idling();
+                    } else {
+                        window.disable_idling();
+                    }
+
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry
+            .register_async(
+                "discord-connect",
+                clone!(
+                    #[strong]
+                    discord,
+                    move |_: IgnoredAny| {
+                        let connected = discord.connect();
+                        async move { Ok(json!({ "connected": connected.await })) }
+                    }
+                ),
+            )
+            .returns(ipc::object(
+                &[("connected", bool::schema())],
+                &["connected"],
+            ));
+
+        registry.register(
+            "discord-disconnect",
+            clone!(
+                #[strong]
+                discord,
+                move |_: IgnoredAny| {
+                    discord.disconnect();
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "discord-set-activity",
+            clone!(
+                #[strong]
+                discord,
+                move |activity: IpcMessageRequestDiscordSetActivity| {
+                    discord.set_activity(DiscordActivity {
+                        details: activity.details,
+                        state: activity.state,
+                        image: activity.image,
+                        buttons: activity
+                            .buttons
+                            .into_iter()
+                            .map(|button| (button.label, button.url))
+                            .collect(),
+                    });
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry.register(
+            "discord-clear-activity",
+            clone!(
+                #[strong]
+                discord,
+                move |_: IgnoredAny| {
+                    discord.clear_activity();
+                    Ok(Value::Null)
+                }
+            ),
+        );
+
+        registry
+    }
+}
+
+fn property_error(name: &str, error: PropertyError) -> IpcError {
+    match error {
+        PropertyError::NotAllowed => IpcError::new(
+            IpcErrorCode::NotAllowed,
+            format!("Property {name} is not allowed"),
+        ),
+        PropertyError::Mpv(message) => IpcError::failed(format!("Property {name}: {message}")),
+    }
+}
+
+fn error_page(message: &str) -> String {
+    let message = message
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    ERROR_PAGE.replace("{message}", &message)
 }
 
 #[glib::object_subclass]
@@ -74,14 +792,19 @@ impl ApplicationImpl for Application {
         let mpris = Mpris::default();
         let discord = Discord::new();
 
-        let startup_url = self.startup_url.borrow();
         let dev_mode = self.dev_mode.get();
 
         let webview = WebView::default();
-        webview.load_uri(&startup_url);
         webview.inject_script(PRELOAD_SCRIPT);
         webview.dev_mode(dev_mode);
 
+        let settings = Settings::new(APP_ID);
+        if self.safe_mode.get() {
+            info!("Safe mode, skipping user mpv config");
+        } else if settings.boolean("mpv-user-config") {
+            video.load_user_config();
+        }
+
         let window = Window::new(&app);
         window.set_property("decorations", self.decorations.get());
         window.set_underlay(&video);
@@ -92,13 +815,42 @@ impl ApplicationImpl for Application {
             window,
             #[weak]
             webview,
-            move |reason| {
+            #[weak]
+            mpris,
+            move |reason, error| {
                 window.enable_idling();
+                mpris.set_status(false, false);
 
                 let message = ipc::create_response(IpcEvent::Mpv(IpcEventMpv::Ended((
                     reason.to_string(),
-                    None,
+                    error,
+                ))));
+                webview.send(&message);
+            }
+        ));
+
+        video.connect_playback_event(clone!(
+            #[weak]
+            app,
+            #[weak]
+            webview,
+            #[weak]
+            mpris,
+            move |name, data| {
+                match name {
+                    "hwdec-changed" => app.imp().send_capabilities(),
+                    "start-file" => {
+                        let entry_id = data["playlistEntryId"].as_i64().unwrap_or_default();
+                        mpris.start_file(entry_id);
+                    }
+                    _ => {}
+                }
+
+                let message = ipc::create_response(IpcEvent::Mpv(IpcEventMpv::Event((
+                    name.to_string(),
+                    data,
                 ))));
+
                 webview.send(&message);
             }
         ));
@@ -116,77 +868,45 @@ impl ApplicationImpl for Application {
             }
         ));
 
-        let deeplink = self.deeplink.clone();
+        let artwork = Rc::new(Artwork::new(webview.network_session()));
+
+        let registry =
+            self.create_ipc_registry(&window, &webview, &video, &mpris, &discord, &artwork);
         webview.connect_ipc(clone!(
             #[weak]
             app,
-            #[weak]
-            window,
-            #[weak]
-            video,
-            #[weak]
-            mpris,
             move |webview: WebView, message: &str| {
-                if let Ok(event) = ipc::parse_request(message) {
-                    match event {
-                        IpcEvent::Init => {
-                            let message = ipc::create_response(IpcEvent::Init);
-                            webview.send(&message);
-                        }
-                        IpcEvent::Ready => {
-                            if let Some(ref uri) = *deeplink.borrow() {
-                                let message =
-                                    ipc::create_response(IpcEvent::OpenMedia(uri.to_string()));
-                                webview.send(&message);
-                            }
-                        }
-                        IpcEvent::Fullscreen(state) => {
-                            window.set_fullscreen(state);
+                let request = ipc::parse_request(message);
 
-                            let message = ipc::create_response(IpcEvent::Fullscreen(state));
-                            webview.send(&message);
-                        }
-                        IpcEvent::MediaStatus(status) => {
-                            mpris.set_status(status);
-
-                            if status {
-                                window.enable_idling();
-                            } else {
-                                window.disable_idling();
-                            }
-                        }
-                        IpcEvent::MediaMetadata((title, artist, artwork)) => {
-                            mpris.set_metadata(title, artist, artwork);
-                        }
-                        IpcEvent::Quit => {
-                            app.quit();
-                        }
-                        IpcEvent::Discord(event) => match event {
-                            IpcEventDiscord::Connect => {
-                                let connected = discord.connect();
-                                let message = ipc::create_response(IpcEvent::Discord(
-                                    IpcEventDiscord::Status(connected),
-                                ));
-                                webview.send(&message);
-                            }
-                            IpcEventDiscord::Disconnect => discord.disconnect(),
-                            IpcEventDiscord::SetActivity((details, state, image)) => {
-                                discord.set_activity(details, state, image)
-                            }
-                            IpcEventDiscord::ClearActivity => discord.clear_activity(),
-                            _ => {}
-                        },
-                        IpcEvent::Mpv(event) => match event {
-                            IpcEventMpv::Observe(name) => video.observe_mpv_property(name),
-                            IpcEventMpv::Command((name, args)) => {
-                                video.send_mpv_command(name, args)
-                            }
-                            IpcEventMpv::Set((name, value)) => video.set_mpv_property(name, value),
-                            _ => {}
-                        },
-                        _ => {}
+                let result: IpcFuture = match request.call {
+                    Ok(IpcCall::Init) => {
+                        let capabilities = app.imp().capabilities();
+
+                        let message = ipc::create_init_response(
+                            request.id,
+                            &registry.methods(),
+                            &registry.schemas(),
+                            &capabilities,
+                        );
+                        webview.send(&message);
+                        return;
                     }
-                }
+                    Ok(IpcCall::Method(name, args)) => registry.call(&name, args),
+                    Err(e) => Box::pin(future::ready(Err(e))),
+                };
+
+                spawn_local!(async move {
+                    let result = result.await;
+
+                    if let Err(ref e) = result {
+                        warn!("Failed to handle ipc request: {}", e.message);
+                    }
+
+                    if let Some(id) = request.id {
+                        let message = ipc::create_reply(id, result);
+                        webview.send(&message);
+                    }
+                });
             }
         ));
 
@@ -198,6 +918,16 @@ impl ApplicationImpl for Application {
             }
         ));
 
+        self.setup_playback_actions(&window, &webview, &video);
+
+        window.connect_dropped(clone!(
+            #[weak]
+            app,
+            move |uris| {
+                app.imp().open_dropped(uris);
+            }
+        ));
+
         webview.connect_open_external(clone!(
             #[weak]
             window,
@@ -215,6 +945,27 @@ impl ApplicationImpl for Application {
             }
         ));
 
+        if let Some(ref server) = *self.server.borrow() {
+            let receiver = server.events();
+
+            spawn_local!(clone!(
+                #[weak]
+                webview,
+                async move {
+                    while let Ok(event) = receiver.recv_async().await {
+                        let event = match event {
+                            ServerEvent::Started => IpcEventServer::Started,
+                            ServerEvent::Restarting(attempt) => IpcEventServer::Restarting(attempt),
+                            ServerEvent::Failed => IpcEventServer::Failed,
+                        };
+
+                        let message = ipc::create_response(IpcEvent::Server(event));
+                        webview.send(&message);
+                    }
+                }
+            ));
+        }
+
         window.connect_visibility(clone!(
             #[weak]
             webview,
@@ -251,11 +1002,43 @@ impl ApplicationImpl for Application {
             }
         ));
 
-        mpris.connect_status(clone!(
+        mpris.connect_play(clone!(
+            #[weak]
+            video,
+            move || {
+                video.set_paused(false);
+            }
+        ));
+
+        mpris.connect_pause(clone!(
+            #[weak]
+            video,
+            move || {
+                video.set_paused(true);
+            }
+        ));
+
+        mpris.connect_play_pause(clone!(
+            #[weak]
+            video,
+            move || {
+                video.toggle_pause();
+            }
+        ));
+
+        mpris.connect_stop(clone!(
             #[weak]
             webview,
-            move |paused| {
-                let message = ipc::create_response(IpcEvent::MediaStatus(paused));
+            #[weak]
+            video,
+            #[weak]
+            mpris,
+            move || {
+                // Stop works even if the web UI is unresponsive, it only gets told to leave the player
+                video.stop();
+                mpris.set_status(false, false);
+
+                let message = ipc::create_response(IpcEvent::MediaStop);
                 webview.send(&message);
             }
         ));
@@ -268,6 +1051,165 @@ impl ApplicationImpl for Application {
             }
         ));
 
+        mpris.connect_next(clone!(
+            #[weak]
+            webview,
+            move || {
+                let message = ipc::create_response(IpcEvent::MediaNext);
+                webview.send(&message);
+            }
+        ));
+
+        mpris.connect_previous(clone!(
+            #[weak]
+            webview,
+            move || {
+                let message = ipc::create_response(IpcEvent::MediaPrevious);
+                webview.send(&message);
+            }
+        ));
+
+        mpris.connect_seek(clone!(
+            #[weak]
+            video,
+            move |position, absolute| {
+                video.seek(position, absolute);
+            }
+        ));
+
+        mpris.connect_volume(clone!(
+            #[weak]
+            video,
+            move |volume| {
+                video.set_volume(volume);
+            }
+        ));
+
+        mpris.connect_rate(clone!(
+            #[weak]
+            video,
+            move |rate| {
+                video.set_speed(rate);
+            }
+        ));
+
+        video.connect_paused_notify(clone!(
+            #[weak]
+            app,
+            #[weak]
+            mpris,
+            move |video| {
+                mpris.set_status(video.playing(), video.paused());
+
+                if let Some(ref pip) = *app.imp().pip.borrow() {
+                    pip.set_paused(video.paused());
+                }
+            }
+        ));
+
+        video.connect_playing_notify(clone!(
+            #[weak]
+            mpris,
+            move |video| {
+                mpris.set_status(video.playing(), video.paused());
+            }
+        ));
+
+        video.connect_time_pos_notify(clone!(
+            #[weak]
+            mpris,
+            move |video| {
+                mpris.set_position(video.time_pos());
+            }
+        ));
+
+        video.connect_duration_notify(clone!(
+            #[weak]
+            mpris,
+            move |video| {
+                mpris.set_length(video.duration());
+            }
+        ));
+
+        video.connect_volume_notify(clone!(
+            #[weak]
+            mpris,
+            move |video| {
+                mpris.set_volume(video.volume());
+            }
+        ));
+
+        video.connect_speed_notify(clone!(
+            #[weak]
+            mpris,
+            move |video| {
+                mpris.set_rate(video.speed());
+            }
+        ));
+
+        video.connect_seeked(clone!(
+            #[weak]
+            mpris,
+            #[weak]
+            video,
+            move || {
+                mpris.seeked(video.time_pos());
+            }
+        ));
+
+        for property in ["playing", "paused", "time-pos", "duration"] {
+            video.connect_notify_local(
+                Some(property),
+                clone!(
+                    #[strong]
+                    discord,
+                    move |video, _| {
+                        let playback = video.playing().then(|| DiscordPlayback {
+                            position: video.time_pos(),
+                            duration: video.duration(),
+                            paused: video.paused(),
+                        });
+
+                        discord.set_playback(playback);
+                    }
+                ),
+            );
+        }
+
+        discord.connect_status(clone!(
+            #[weak]
+            webview,
+            move |connected| {
+                let message =
+                    ipc::create_response(IpcEvent::Discord(IpcEventDiscord::Status(connected)));
+                webview.send(&message);
+            }
+        ));
+
+        tray.connect_probed(clone!(
+            #[weak]
+            app,
+            move || {
+                app.imp().send_capabilities();
+            }
+        ));
+
+        mpris.connect_probed(clone!(
+            #[weak]
+            app,
+            move || {
+                app.imp().send_capabilities();
+            }
+        ));
+
+        window.connect_inhibit_probed(clone!(
+            #[weak]
+            app,
+            move || {
+                app.imp().send_capabilities();
+            }
+        ));
+
         mpris.start(APP_ID, APP_NAME);
 
         window.present();
@@ -276,6 +1218,10 @@ impl ApplicationImpl for Application {
         *self.mpris.borrow_mut() = Some(mpris);
         *self.window.borrow_mut() = Some(window);
         *self.webview.borrow_mut() = Some(webview);
+        *self.video.borrow_mut() = Some(video);
+        *self.discord.borrow_mut() = Some(discord);
+
+        self.load_startup_url();
     }
 
     fn open(&self, files: &[gtk::gio::File], hint: &str) {
@@ -284,13 +1230,7 @@ impl ApplicationImpl for Application {
         if let Some(file) = files.first() {
             let uri = file.uri().to_string();
             if uri.starts_with(URI_SCHEME) {
-                let mut deeplink = self.deeplink.borrow_mut();
-                *deeplink = Some(uri.clone());
-
-                if let Some(ref webview) = *self.webview.borrow() {
-                    let message = ipc::create_response(IpcEvent::OpenMedia(uri));
-                    webview.send(&message);
-                }
+                self.open_media(uri);
             }
         }
 
@@ -298,10 +1238,20 @@ impl ApplicationImpl for Application {
     }
 
     fn shutdown(&self) {
+        if let Some(pip) = self.pip.take() {
+            pip.destroy();
+        }
+
         if let Some(window) = self.window.take() {
             window.destroy();
         }
 
+        if let Some(server) = self.server.take()
+            && let Err(e) = server.stop()
+        {
+            error!("Failed to stop server: {e}");
+        }
+
         self.parent_shutdown();
     }
 }
@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+/// Features of the shell that are actually usable on this machine,
+/// so the web UI can hide the ones that are not.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IpcCapabilities {
+    pub tray: bool,
+    pub mpris: bool,
+    pub discord: bool,
+    pub idle_inhibit: bool,
+    pub mpv: IpcCapabilitiesMpv,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IpcCapabilitiesMpv {
+    pub version: Option<String>,
+    /// Hardware decoding interop loaded by the renderer, if any.
+    pub hwdec_interop: Option<String>,
+    /// Hardware decoder in use for the current file, if any.
+    pub hwdec_current: Option<String>,
+}
@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpcErrorCode {
+    InvalidRequest,
+    UnknownMethod,
+    InvalidArguments,
+    NotAllowed,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct IpcError {
+    pub code: IpcErrorCode,
+    pub message: String,
+}
+
+impl IpcError {
+    pub fn new(code: IpcErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn unknown_method(method: &str) -> Self {
+        Self::new(
+            IpcErrorCode::UnknownMethod,
+            format!("Unknown method: {method}"),
+        )
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self::new(IpcErrorCode::Failed, message)
+    }
+}
@@ -1,19 +1,75 @@
+mod capabilities;
+mod error;
 pub mod event;
-mod request;
+mod registry;
+pub mod request;
 mod response;
+mod schema;
+
+use std::collections::BTreeMap;
 
 use event::IpcEvent;
+use request::{IpcCall, IpcMessageRequest};
 use response::IpcMessageResponse;
-use tracing::warn;
+use serde_json::Value;
+
+pub use capabilities::{IpcCapabilities, IpcCapabilitiesMpv};
+pub use error::{IpcError, IpcErrorCode};
+pub use registry::{IpcFuture, IpcMethodSchema, IpcRegistry};
+pub use schema::{IpcSchema, object};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const PROTOCOL_VERSION: u32 = 1;
 const TRANSPORT_NAME: &str = "transport";
 
-pub fn parse_request(data: &str) -> Result<IpcEvent, ()> {
-    IpcEvent::try_from(data).map_err(|e| warn!("{e}"))
+pub struct IpcRequest {
+    pub id: Option<u64>,
+    pub call: Result<IpcCall, IpcError>,
+}
+
+pub fn parse_request(data: &str) -> IpcRequest {
+    let value = match serde_json::from_str::<Value>(data) {
+        Ok(value) => value,
+        Err(e) => {
+            return IpcRequest {
+                id: None,
+                call: Err(IpcError::new(
+                    IpcErrorCode::InvalidRequest,
+                    format!("Failed to parse request: {e}"),
+                )),
+            };
+        }
+    };
+
+    let id = value.get("id").and_then(Value::as_u64);
+    let call = serde_json::from_value::<IpcMessageRequest>(value)
+        .map_err(|e| {
+            IpcError::new(
+                IpcErrorCode::InvalidRequest,
+                format!("Failed to parse request: {e}"),
+            )
+        })
+        .and_then(IpcCall::try_from);
+
+    IpcRequest { id, call }
 }
 
 pub fn create_response(event: IpcEvent) -> String {
-    let message = IpcMessageResponse::try_from(event).ok();
+    let message = IpcMessageResponse::from(event);
+    serde_json::to_string(&message).expect("Failed to convert IpcMessage to string")
+}
+
+pub fn create_init_response(
+    id: Option<u64>,
+    methods: &[&str],
+    schemas: &BTreeMap<&str, IpcMethodSchema>,
+    capabilities: &IpcCapabilities,
+) -> String {
+    let message = IpcMessageResponse::init(id, methods, schemas, capabilities);
+    serde_json::to_string(&message).expect("Failed to convert IpcMessage to string")
+}
+
+pub fn create_reply(id: u64, result: Result<Value, IpcError>) -> String {
+    let message = IpcMessageResponse::reply(id, result);
     serde_json::to_string(&message).expect("Failed to convert IpcMessage to string")
 }
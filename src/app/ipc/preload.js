@@ -1,7 +1,23 @@
+const INVOKE_METHOD_TYPE = 6;
+const RESPONSE_TYPE = 10;
+// Keep call ids apart from the ones used by the web channel transport
+const CALL_ID_OFFSET = 2 ** 32;
+
 const createIpc = () => {
     let listeners = [];
+    let calls = new Map();
+    let nextCallId = CALL_ID_OFFSET;
 
     globalThis.__postMessage = (data) => {
+        const message = JSON.parse(data);
+
+        if (message && message.type === RESPONSE_TYPE && calls.has(message.id)) {
+            const { resolve, reject } = calls.get(message.id);
+            calls.delete(message.id);
+
+            return message.error ? reject(message.error) : resolve(message.data);
+        }
+
         listeners.forEach((listener) => {
             listener({ data });
         });
@@ -11,6 +27,17 @@ const createIpc = () => {
         globalThis.webkit.messageHandlers.ipc.postMessage(data);
     };
 
+    const call = (method, data) => new Promise((resolve, reject) => {
+        const id = nextCallId++;
+        calls.set(id, { resolve, reject });
+
+        postMessage(JSON.stringify({
+            id,
+            type: INVOKE_METHOD_TYPE,
+            args: data === undefined ? [method] : [method, data],
+        }));
+    });
+
     const addEventListener = (name, listener) => {
         if (name !== 'message')
             throw Error('Unsupported event');
@@ -26,6 +53,7 @@ const createIpc = () => {
     };
 
     return {
+        call,
         postMessage,
         addEventListener,
         removeEventListener,
@@ -0,0 +1,119 @@
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    future,
+    pin::Pin,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use super::{
+    error::{IpcError, IpcErrorCode},
+    schema::{self, IpcSchema},
+};
+
+pub type IpcResult = Result<Value, IpcError>;
+pub type IpcFuture = Pin<Box<dyn Future<Output = IpcResult>>>;
+
+type IpcHandler = Box<dyn Fn(Option<Value>) -> IpcFuture>;
+
+/// Argument and return shapes of a method, see [`IpcSchema`].
+#[derive(Serialize, Debug, Clone)]
+pub struct IpcMethodSchema {
+    args: Value,
+    returns: Value,
+}
+
+impl IpcMethodSchema {
+    /// Methods reply with `null` unless they declare otherwise.
+    pub fn returns(&mut self, schema: Value) -> &mut Self {
+        self.returns = schema;
+        self
+    }
+}
+
+struct IpcMethod {
+    handler: IpcHandler,
+    schema: IpcMethodSchema,
+}
+
+/// Methods the web UI can call on the shell, keyed by name.
+///
+/// Arguments are deserialized into the type taken by the handler before it runs,
+/// methods without arguments can use [`serde::de::IgnoredAny`].
+#[derive(Default)]
+pub struct IpcRegistry {
+    methods: BTreeMap<&'static str, IpcMethod>,
+}
+
+impl IpcRegistry {
+    pub fn register<A, F>(&mut self, name: &'static str, handler: F) -> &mut IpcMethodSchema
+    where
+        A: DeserializeOwned + IpcSchema + 'static,
+        F: Fn(A) -> IpcResult + 'static,
+    {
+        self.register_async(name, move |args: A| future::ready(handler(args)))
+    }
+
+    /// Registers a method whose reply is only known once the returned future resolves.
+    pub fn register_async<A, F, R>(
+        &mut self,
+        name: &'static str,
+        handler: F,
+    ) -> &mut IpcMethodSchema
+    where
+        A: DeserializeOwned + IpcSchema + 'static,
+        F: Fn(A) -> R + 'static,
+        R: Future<Output = IpcResult> + 'static,
+    {
+        let handler = move |args: Option<Value>| -> IpcFuture {
+            let args = serde_json::from_value::<A>(args.unwrap_or_default()).map_err(|e| {
+                IpcError::new(
+                    IpcErrorCode::InvalidArguments,
+                    format!("Invalid {name} arguments: {e}"),
+                )
+            });
+
+            match args {
+                Ok(args) => Box::pin(handler(args)),
+                Err(e) => Box::pin(future::ready(Err(e))),
+            }
+        };
+
+        let method = IpcMethod {
+            handler: Box::new(handler),
+            schema: IpcMethodSchema {
+                args: A::schema(),
+                returns: schema::null(),
+            },
+        };
+
+        let method = match self.methods.entry(name) {
+            Entry::Occupied(mut entry) => {
+                entry.insert(method);
+                entry.into_mut()
+            }
+            Entry::Vacant(entry) => entry.insert(method),
+        };
+
+        &mut method.schema
+    }
+
+    pub fn methods(&self) -> Vec<&'static str> {
+        self.methods.keys().copied().collect()
+    }
+
+    pub fn schemas(&self) -> BTreeMap<&'static str, IpcMethodSchema> {
+        self.methods
+            .iter()
+            .map(|(name, method)| (*name, method.schema.clone()))
+            .collect()
+    }
+
+    pub fn call(&self, name: &str, args: Option<Value>) -> IpcFuture {
+        match self.methods.get(name) {
+            Some(method) => (method.handler)(args),
+            None => Box::pin(future::ready(Err(IpcError::unknown_method(name)))),
+        }
+    }
+}
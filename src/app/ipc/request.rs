@@ -1,9 +1,15 @@
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{Value, json};
 
-use crate::app::ipc::event::IpcEventDiscord;
+use crate::app::video::ScreenshotFormat;
 
-use super::event::{IpcEvent, IpcEventMpv};
+use super::{
+    error::{IpcError, IpcErrorCode},
+    schema::{IpcSchema, object},
+};
+
+const TYPE_INIT: u8 = 3;
+const TYPE_INVOKE_METHOD: u8 = 6;
 
 #[derive(Deserialize, Debug)]
 pub struct IpcMessageRequest {
@@ -12,134 +18,149 @@ pub struct IpcMessageRequest {
     args: Option<serde_json::Value>,
 }
 
+#[derive(Debug)]
+pub enum IpcCall {
+    Init,
+    Method(String, Option<Value>),
+}
+
 #[derive(Deserialize, Debug)]
 pub struct IpcMessageRequestWinSetVisilibty {
-    fullscreen: bool,
+    pub fullscreen: bool,
 }
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct IpcMessageRequestMediaMetadata {
-    title: String,
-    artist: Option<String>,
-    art_url: Option<String>,
+    pub id: Option<String>,
+    pub title: String,
+    pub artist: Option<String>,
+    pub art_url: Option<String>,
+    pub series: Option<String>,
+    pub episode: Option<i32>,
+    pub url: Option<String>,
+    /// Length in seconds, until mpv reports the actual duration.
+    pub duration: Option<f64>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct IpcMessageRequestMediaStatus {
-    paused: bool,
+    pub paused: bool,
 }
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct IpcMessageRequestDiscordSetActivity {
-    details: String,
-    state: String,
-    image: Option<String>,
+    pub details: String,
+    pub state: String,
+    pub image: Option<String>,
+    #[serde(default)]
+    pub buttons: Vec<IpcMessageRequestDiscordButton>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IpcMessageRequestDiscordButton {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct IpcMessageRequestScreenshot {
+    #[serde(default)]
+    pub format: ScreenshotFormat,
+    #[serde(default)]
+    pub subtitles: bool,
 }
 
-impl TryFrom<IpcMessageRequest> for IpcEvent {
-    type Error = String;
+impl IpcSchema for IpcMessageRequestWinSetVisilibty {
+    fn schema() -> Value {
+        object(&[("fullscreen", bool::schema())], &["fullscreen"])
+    }
+}
+
+impl IpcSchema for IpcMessageRequestMediaMetadata {
+    fn schema() -> Value {
+        object(
+            &[
+                ("id", Option::<String>::schema()),
+                ("title", String::schema()),
+                ("artist", Option::<String>::schema()),
+                ("artUrl", Option::<String>::schema()),
+                ("series", Option::<String>::schema()),
+                ("episode", Option::<i32>::schema()),
+                ("url", Option::<String>::schema()),
+                ("duration", Option::<f64>::schema()),
+            ],
+            &["title"],
+        )
+    }
+}
+
+impl IpcSchema for IpcMessageRequestMediaStatus {
+    fn schema() -> Value {
+        object(&[("paused", bool::schema())], &["paused"])
+    }
+}
+
+impl IpcSchema for IpcMessageRequestDiscordSetActivity {
+    fn schema() -> Value {
+        object(
+            &[
+                ("details", String::schema()),
+                ("state", String::schema()),
+                ("image", Option::<String>::schema()),
+                ("buttons", Vec::<IpcMessageRequestDiscordButton>::schema()),
+            ],
+            &["details", "state"],
+        )
+    }
+}
+
+impl IpcSchema for IpcMessageRequestDiscordButton {
+    fn schema() -> Value {
+        object(
+            &[("label", String::schema()), ("url", String::schema())],
+            &["label", "url"],
+        )
+    }
+}
+
+impl IpcSchema for IpcMessageRequestScreenshot {
+    fn schema() -> Value {
+        object(
+            &[
+                ("format", json!({ "enum": ["png", "jpeg"] })),
+                ("subtitles", bool::schema()),
+            ],
+            &[],
+        )
+    }
+}
+
+impl TryFrom<IpcMessageRequest> for IpcCall {
+    type Error = IpcError;
 
     fn try_from(value: IpcMessageRequest) -> Result<Self, Self::Error> {
+        let invalid_request = |message: &str| IpcError::new(IpcErrorCode::InvalidRequest, message);
+
         match value.r#type {
-            3 => Ok(IpcEvent::Init),
-            6 => match value.args {
-                Some(args) => {
-                    let args: Vec<Value> =
-                        serde_json::from_value(args).map_err(|_| "Invalid arguments")?;
-                    let name = args.first().and_then(Value::as_str).ok_or("Invalid name")?;
-                    let data = args.get(1).cloned();
-
-                    match data {
-                        Some(data) => match name {
-                            "win-set-visibility" => {
-                                let data: IpcMessageRequestWinSetVisilibty =
-                                    serde_json::from_value(data)
-                                        .map_err(|_| "Invalid win-set-visibility object")?;
-
-                                Ok(IpcEvent::Fullscreen(data.fullscreen))
-                            }
-                            "mpv-command" => {
-                                let data: Vec<String> = serde_json::from_value(data)
-                                    .map_err(|_| "Invalid mpv-command arguments")?;
-                                let name = data[0].clone();
-
-                                let mut args = vec![];
-                                for arg in data.iter().skip(1) {
-                                    args.push(arg.clone());
-                                }
-
-                                Ok(IpcEvent::Mpv(IpcEventMpv::Command((name, args))))
-                            }
-                            "mpv-observe-prop" => {
-                                let name = data.as_str().ok_or("Invalid mpv-observe-prop name")?;
-                                Ok(IpcEvent::Mpv(IpcEventMpv::Observe(name.to_owned())))
-                            }
-                            "mpv-set-prop" => {
-                                let key_value: Vec<Value> = serde_json::from_value(data)
-                                    .map_err(|_| "Invalid mpv-set-prop arguments")?;
-
-                                let name = key_value[0]
-                                    .as_str()
-                                    .ok_or("Invalid mpv-set-prop name")?
-                                    .to_owned();
-
-                                let value = key_value
-                                    .get(1)
-                                    .ok_or("Invalid mpv-set-prop value")?
-                                    .to_owned();
-
-                                Ok(IpcEvent::Mpv(IpcEventMpv::Set((name, value))))
-                            }
-                            "media.metadata" => {
-                                let data: IpcMessageRequestMediaMetadata =
-                                    serde_json::from_value(data)
-                                        .map_err(|_| "Invalid media.metadata object")?;
-
-                                Ok(IpcEvent::MediaMetadata((
-                                    data.title,
-                                    data.artist,
-                                    data.art_url,
-                                )))
-                            }
-                            "media.status" => {
-                                let data: IpcMessageRequestMediaStatus =
-                                    serde_json::from_value(data)
-                                        .map_err(|_| "Invalid media.status object")?;
-
-                                Ok(IpcEvent::MediaStatus(data.paused))
-                            }
-                            "discord-connect" => Ok(IpcEvent::Discord(IpcEventDiscord::Connect)),
-                            "discord-disconnect" => {
-                                Ok(IpcEvent::Discord(IpcEventDiscord::Disconnect))
-                            }
-                            "discord-set-activity" => {
-                                let data: IpcMessageRequestDiscordSetActivity =
-                                    serde_json::from_value(data)
-                                        .map_err(|_| "Invalid discord-set-activity object")?;
-
-                                Ok(IpcEvent::Discord(IpcEventDiscord::SetActivity((
-                                    data.details,
-                                    data.state,
-                                    data.image,
-                                ))))
-                            }
-                            "discord-clear-activity" => {
-                                Ok(IpcEvent::Discord(IpcEventDiscord::ClearActivity))
-                            }
-                            method => Err(format!("Invalid method: {method}")),
-                        },
-                        None => match name {
-                            "app-ready" => Ok(IpcEvent::Ready),
-                            "quit" => Ok(IpcEvent::Quit),
-                            method => Err(format!("Invalid method: {method}")),
-                        },
-                    }
-                }
-                None => Err("Invalid arguments".into()),
-            },
-            r#type => Err(format!("Invalid type: {}", r#type)),
+            TYPE_INIT => Ok(IpcCall::Init),
+            TYPE_INVOKE_METHOD => {
+                let args = value
+                    .args
+                    .ok_or_else(|| invalid_request("Invalid arguments"))?;
+                let args: Vec<Value> = serde_json::from_value(args)
+                    .map_err(|_| invalid_request("Invalid arguments"))?;
+
+                let mut args = args.into_iter();
+                let name = args
+                    .next()
+                    .and_then(|name| name.as_str().map(str::to_owned))
+                    .ok_or_else(|| invalid_request("Invalid name"))?;
+
+                Ok(IpcCall::Method(name, args.next()))
+            }
+            r#type => Err(invalid_request(&format!("Invalid type: {}", r#type))),
         }
     }
 }
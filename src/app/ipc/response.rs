@@ -1,119 +1,164 @@
+use std::collections::BTreeMap;
+
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{Value, json};
 
-use crate::app::ipc::event::IpcEventDiscord;
+use crate::app::ipc::event::{IpcEventDiscord, IpcEventServer};
 
 use super::{
-    TRANSPORT_NAME, VERSION,
+    PROTOCOL_VERSION, TRANSPORT_NAME, VERSION,
+    capabilities::IpcCapabilities,
+    error::IpcError,
     event::{IpcEvent, IpcEventMpv},
+    registry::IpcMethodSchema,
 };
 
+const TYPE_SIGNAL: u8 = 1;
+const TYPE_INIT: u8 = 3;
+const TYPE_RESPONSE: u8 = 10;
+
 #[derive(Serialize, Debug)]
 pub struct IpcMessageResponse {
-    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
     r#type: u8,
-    object: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<serde_json::Value>,
+    object: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    args: Option<serde_json::Value>,
+    data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<IpcError>,
 }
 
-impl TryFrom<IpcEvent> for IpcMessageResponse {
-    type Error = &'static str;
+impl IpcMessageResponse {
+    pub fn init(
+        id: Option<u64>,
+        methods: &[&str],
+        schemas: &BTreeMap<&str, IpcMethodSchema>,
+        capabilities: &IpcCapabilities,
+    ) -> Self {
+        Self {
+            id,
+            r#type: TYPE_INIT,
+            object: Some(TRANSPORT_NAME.to_owned()),
+            data: Some(json!({
+                "transport": {
+                    "properties": [
+                        [],
+                        ["", "shellVersion", "", VERSION],
+                        ["", "protocolVersion", "", PROTOCOL_VERSION],
+                        ["", "shellMethods", "", methods],
+                        ["", "shellMethodSchemas", "", schemas],
+                        ["", "shellCapabilities", "", capabilities],
+                    ],
+                    "signals": [],
+                    "methods": [["onEvent"]]
+                }
+            })),
+            args: None,
+            error: None,
+        }
+    }
+
+    pub fn reply(id: u64, result: Result<Value, IpcError>) -> Self {
+        let (data, error) = match result {
+            Ok(data) => (Some(data), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Self {
+            id: Some(id),
+            r#type: TYPE_RESPONSE,
+            object: None,
+            data,
+            args: None,
+            error,
+        }
+    }
 
-    fn try_from(value: IpcEvent) -> Result<Self, Self::Error> {
+    fn signal(args: Value) -> Self {
+        Self {
+            id: None,
+            r#type: TYPE_SIGNAL,
+            object: Some(TRANSPORT_NAME.to_owned()),
+            data: None,
+            args: Some(args),
+            error: None,
+        }
+    }
+}
+
+impl From<IpcEvent> for IpcMessageResponse {
+    fn from(value: IpcEvent) -> Self {
         match value {
-            IpcEvent::Init => Ok(IpcMessageResponse {
-                id: 0,
-                r#type: 3,
-                object: TRANSPORT_NAME.to_owned(),
-                args: None,
-                data: Some(json!({
-                    "transport": {
-                        "properties": [[], ["", "shellVersion", "", VERSION]],
-                        "signals": [],
-                        "methods": [["onEvent"]]
-                    }
-                })),
-            }),
-            IpcEvent::Fullscreen(state) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
-                    "win-visibility-changed",
-                    {
-                        "visible": true,
-                        "visibility": 1,
-                        "isFullscreen": state,
-                    }
-                ])),
-            }),
-            IpcEvent::Visibility(state) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
-                    "win-visibility-changed",
-                    {
-                        "visible": state,
-                        "visibility": state as u32,
-                        "isFullscreen": false,
-                    }
-                ])),
-            }),
-            IpcEvent::OpenMedia(deeplink) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!(["open-media", deeplink])),
-            }),
-            IpcEvent::Mpv(IpcEventMpv::Change((name, value))) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!(["mpv-prop-change", {
+            IpcEvent::Fullscreen(state) => IpcMessageResponse::signal(json!([
+                "win-visibility-changed",
+                {
+                    "visible": true,
+                    "visibility": 1,
+                    "isFullscreen": state,
+                }
+            ])),
+            IpcEvent::Visibility(state) => IpcMessageResponse::signal(json!([
+                "win-visibility-changed",
+                {
+                    "visible": state,
+                    "visibility": state as u32,
+                    "isFullscreen": false,
+                }
+            ])),
+            IpcEvent::OpenMedia(deeplink) => {
+                IpcMessageResponse::signal(json!(["open-media", deeplink]))
+            }
+            IpcEvent::Mpv(IpcEventMpv::Change((name, value))) => {
+                IpcMessageResponse::signal(json!(["mpv-prop-change", {
                     "name": name,
                     "data": value,
-                }])),
-            }),
-            IpcEvent::Mpv(IpcEventMpv::Ended((reason, error))) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
+                }]))
+            }
+            IpcEvent::Mpv(IpcEventMpv::Ended((reason, error))) => {
+                IpcMessageResponse::signal(json!([
                     "mpv-event-ended",
                     {
                         "reason": reason,
                         "error": error,
                     }
-                ])),
-            }),
-            IpcEvent::MediaStatus(paused) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!(["media.status", {
-                    "paused": paused
-                }])),
-            }),
-            IpcEvent::Discord(IpcEventDiscord::Status(connected)) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!(["discord-status", {
+                ]))
+            }
+            IpcEvent::Mpv(IpcEventMpv::Event((name, data))) => {
+                IpcMessageResponse::signal(json!([format!("mpv-event-{name}"), data]))
+            }
+            IpcEvent::MediaStop => IpcMessageResponse::signal(json!(["media.stop"])),
+            IpcEvent::MediaNext => IpcMessageResponse::signal(json!(["media.next"])),
+            IpcEvent::MediaPrevious => IpcMessageResponse::signal(json!(["media.previous"])),
+            IpcEvent::Discord(IpcEventDiscord::Status(connected)) => {
+                IpcMessageResponse::signal(json!(["discord-status", {
                     "connected": connected
-                }])),
-            }),
-            _ => Err("Failed to convert IpcEvent to IpcMessageResponse"),
+                }]))
+            }
+            IpcEvent::Capabilities(capabilities) => {
+                IpcMessageResponse::signal(json!(["capabilities-changed", capabilities]))
+            }
+            IpcEvent::Pip(active) => {
+                IpcMessageResponse::signal(json!(["pip-changed", { "active": active }]))
+            }
+            IpcEvent::Screenshot(path) => {
+                IpcMessageResponse::signal(json!(["screenshot", { "path": path }]))
+            }
+            IpcEvent::Server(event) => {
+                let status = match event {
+                    IpcEventServer::Started => json!({ "status": "started" }),
+                    IpcEventServer::Restarting(attempt) => json!({
+                        "status": "restarting",
+                        "attempt": attempt,
+                    }),
+                    IpcEventServer::Failed => json!({ "status": "failed" }),
+                };
+
+                IpcMessageResponse::signal(json!(["server-status", status]))
+            }
         }
     }
 }
@@ -0,0 +1,86 @@
+use serde::de::IgnoredAny;
+use serde_json::{Map, Value, json};
+
+/// Shape of a value exchanged over ipc, described with a subset of JSON Schema.
+///
+/// Advertised in the init handshake so the web UI can introspect shell methods.
+pub trait IpcSchema {
+    fn schema() -> Value;
+}
+
+/// Describes an object with the given properties, of which `required` must be present.
+pub fn object(properties: &[(&str, Value)], required: &[&str]) -> Value {
+    let properties = properties
+        .iter()
+        .map(|(name, schema)| (name.to_string(), schema.clone()))
+        .collect::<Map<_, _>>();
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+pub fn null() -> Value {
+    json!({ "type": "null" })
+}
+
+impl IpcSchema for IgnoredAny {
+    fn schema() -> Value {
+        null()
+    }
+}
+
+impl IpcSchema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+impl IpcSchema for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl IpcSchema for bool {
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl IpcSchema for i32 {
+    fn schema() -> Value {
+        json!({ "type": "integer" })
+    }
+}
+
+impl IpcSchema for f64 {
+    fn schema() -> Value {
+        json!({ "type": "number" })
+    }
+}
+
+impl<T: IpcSchema> IpcSchema for Option<T> {
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), null()] })
+    }
+}
+
+impl<T: IpcSchema> IpcSchema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<A: IpcSchema, B: IpcSchema> IpcSchema for (A, B) {
+    fn schema() -> Value {
+        json!({
+            "type": "array",
+            "prefixItems": [A::schema(), B::schema()],
+            "minItems": 2,
+            "maxItems": 2,
+        })
+    }
+}
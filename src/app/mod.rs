@@ -1,14 +1,17 @@
+mod artwork;
 mod config;
 mod dialogs;
 mod discord;
 mod imp;
 mod ipc;
 mod mpris;
+mod pip;
 mod tray;
 mod video;
 mod webview;
 mod window;
 
+use adw::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{
     CssProvider,
     gdk::Display,
@@ -18,11 +21,16 @@ use gtk::{
 };
 use itertools::Itertools;
 
-use crate::app::{
-    config::{APP_ID, APP_NAME, STYLE},
-    dialogs::{about::AboutDialog, preferences::PreferencesDialog},
+use crate::{
+    app::{
+        config::{APP_NAME, STYLE},
+        dialogs::{about::AboutDialog, preferences::PreferencesDialog},
+    },
+    server::Server,
 };
 
+pub use config::APP_ID;
+
 glib::wrapper! {
     pub struct Application(ObjectSubclass<imp::Application>)
     @extends gio::Application, gtk::Application, adw::Application,
@@ -51,6 +59,10 @@ impl Application {
         self.run_with_args(&program)
     }
 
+    pub fn set_server(&self, server: Server) {
+        *self.imp().server.borrow_mut() = Some(server);
+    }
+
     fn setup_actions(&self) {
         let quit_action = ActionEntry::builder("quit")
             .activate(|app: &Self, _, _| {
@@ -76,12 +88,24 @@ impl Application {
             })
             .build();
 
-        self.add_action_entries([quit_action, show_preferences_action, show_about_action]);
+        let screenshot_action = ActionEntry::builder("screenshot")
+            .activate(|app: &Self, _, _| {
+                app.imp().take_screenshot();
+            })
+            .build();
+
+        self.add_action_entries([
+            quit_action,
+            show_preferences_action,
+            show_about_action,
+            screenshot_action,
+        ]);
     }
 
     fn setup_accels(&self) {
         self.set_accels_for_action("app.quit", &["<Control>q"]);
         self.set_accels_for_action("app.show-preferences", &["<Control>comma"]);
+        self.set_accels_for_action("app.screenshot", &["<Control><Shift>s"]);
     }
 
     fn setup_css(&self) {
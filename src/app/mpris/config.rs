@@ -0,0 +1,7 @@
+pub const TRACK_ID_PREFIX: &str = "/com/stremio/Stremio/Track";
+/// Track ids of files loaded before the web UI sent their metadata.
+pub const FILE_TRACK_ID_PREFIX: &str = "/com/stremio/Stremio/File";
+
+pub const MINIMUM_RATE: f64 = 0.25;
+pub const MAXIMUM_RATE: f64 = 4.0;
+pub const MAXIMUM_VOLUME: f64 = 100.0;
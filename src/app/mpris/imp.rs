@@ -1,22 +1,41 @@
 use std::{
-    cell::{OnceCell, RefCell},
+    cell::{Cell, OnceCell, RefCell},
     rc::Rc,
 };
 
 use gtk::glib::{self, subclass::prelude::*};
-use mpris_server::{Metadata, PlaybackStatus, Player};
+use mpris_server::{Metadata, PlaybackStatus, Player, Time, TrackId};
 use tracing::error;
 
 use crate::spawn_local;
 
-type StatusCallback = Box<dyn Fn(bool)>;
-type RaiseCallback = Box<dyn Fn()>;
+use super::{
+    MediaMetadata,
+    config::{FILE_TRACK_ID_PREFIX, MAXIMUM_RATE, MAXIMUM_VOLUME, MINIMUM_RATE, TRACK_ID_PREFIX},
+};
+
+type ControlCallback = Box<dyn Fn()>;
+type SeekCallback = Box<dyn Fn(f64, bool)>;
+type ValueCallback = Box<dyn Fn(f64)>;
 
 #[derive(Default)]
 pub struct Mpris {
     mpris: Rc<OnceCell<Player>>,
-    status_callback: Rc<RefCell<Option<StatusCallback>>>,
-    raise_callback: Rc<RefCell<Option<RaiseCallback>>>,
+    metadata: Rc<RefCell<Metadata>>,
+    /// Track id of the loaded file, used while the web UI gave no usable one.
+    file_track_id: RefCell<Option<TrackId>>,
+    playing: Cell<bool>,
+    play_callback: Rc<RefCell<Option<ControlCallback>>>,
+    pause_callback: Rc<RefCell<Option<ControlCallback>>>,
+    play_pause_callback: Rc<RefCell<Option<ControlCallback>>>,
+    stop_callback: Rc<RefCell<Option<ControlCallback>>>,
+    raise_callback: Rc<RefCell<Option<ControlCallback>>>,
+    next_callback: Rc<RefCell<Option<ControlCallback>>>,
+    previous_callback: Rc<RefCell<Option<ControlCallback>>>,
+    seek_callback: Rc<RefCell<Option<SeekCallback>>>,
+    volume_callback: Rc<RefCell<Option<ValueCallback>>>,
+    rate_callback: Rc<RefCell<Option<ValueCallback>>>,
+    probed_callback: Rc<RefCell<Option<ControlCallback>>>,
 }
 
 #[glib::object_subclass]
@@ -29,81 +48,371 @@ impl ObjectSubclass for Mpris {
 impl Mpris {
     pub fn start(&self, id: &'static str, name: &'static str) {
         let mpris = self.mpris.clone();
-        let status_callback = self.status_callback.clone();
+        let metadata = self.metadata.clone();
+        let play_callback = self.play_callback.clone();
+        let pause_callback = self.pause_callback.clone();
+        let play_pause_callback = self.play_pause_callback.clone();
+        let stop_callback = self.stop_callback.clone();
         let raise_callback = self.raise_callback.clone();
+        let next_callback = self.next_callback.clone();
+        let previous_callback = self.previous_callback.clone();
+        let seek_callback = self.seek_callback.clone();
+        let volume_callback = self.volume_callback.clone();
+        let rate_callback = self.rate_callback.clone();
+        let probed_callback = self.probed_callback.clone();
 
         spawn_local!(async move {
-            let player = Player::builder(name)
+            let player = match Player::builder(name)
                 .identity(name)
                 .desktop_entry(id)
                 .can_play(true)
                 .can_pause(true)
+                .playback_status(PlaybackStatus::Stopped)
                 .can_raise(true)
+                .can_seek(false)
+                .can_control(true)
                 .can_go_previous(false)
                 .can_go_next(false)
+                .minimum_rate(MINIMUM_RATE)
+                .maximum_rate(MAXIMUM_RATE)
                 .build()
                 .await
-                .expect("Failed to start MPRIS server");
+            {
+                Ok(player) => player,
+                Err(e) => {
+                    error!("Failed to start MPRIS server: {e}");
 
-            if let Some(callback) = status_callback.borrow_mut().take() {
-                player.connect_play_pause(move |player| {
-                    let paused = matches!(player.playback_status(), PlaybackStatus::Playing);
-                    callback(paused);
-                });
+                    if let Some(callback) = probed_callback.borrow_mut().take() {
+                        callback();
+                    }
+
+                    return;
+                }
+            };
+
+            if let Some(callback) = play_callback.borrow_mut().take() {
+                player.connect_play(move |_| callback());
+            }
+
+            if let Some(callback) = pause_callback.borrow_mut().take() {
+                player.connect_pause(move |_| callback());
+            }
+
+            if let Some(callback) = play_pause_callback.borrow_mut().take() {
+                player.connect_play_pause(move |_| callback());
+            }
+
+            if let Some(callback) = stop_callback.borrow_mut().take() {
+                player.connect_stop(move |_| callback());
             }
 
             if let Some(callback) = raise_callback.borrow_mut().take() {
                 player.connect_raise(move |_| callback());
             }
 
+            if let Some(callback) = next_callback.borrow_mut().take() {
+                player.connect_next(move |_| callback());
+            }
+
+            if let Some(callback) = previous_callback.borrow_mut().take() {
+                player.connect_previous(move |_| callback());
+            }
+
+            if let Some(callback) = seek_callback.borrow_mut().take() {
+                let callback = Rc::new(callback);
+
+                let seek = callback.clone();
+                player.connect_seek(move |player, offset| {
+                    if player.can_seek() {
+                        seek(to_seconds(offset), false);
+                    }
+                });
+
+                player.connect_set_position(move |player, track_id, position| {
+                    // Requests for a previous track are stale and must be ignored
+                    if player.can_seek() && metadata.borrow().trackid().as_ref() == Some(track_id) {
+                        callback(to_seconds(position), true);
+                    }
+                });
+            }
+
+            if let Some(callback) = volume_callback.borrow_mut().take() {
+                player.connect_set_volume(move |_, volume| {
+                    callback(volume.clamp(0.0, 1.0) * MAXIMUM_VOLUME);
+                });
+            }
+
+            if let Some(callback) = rate_callback.borrow_mut().take() {
+                player.connect_set_rate(move |_, rate| {
+                    // A rate of 0 means pause per the spec, which clients send through Pause
+                    if rate > 0.0 {
+                        callback(rate.clamp(MINIMUM_RATE, MAXIMUM_RATE));
+                    }
+                });
+            }
+
             let player = mpris.get_or_init(|| player);
+
+            if let Some(callback) = probed_callback.borrow_mut().take() {
+                callback();
+            }
+
             player.run().await;
         });
     }
 
-    pub fn set_status(&self, paused: bool) {
+    pub fn is_available(&self) -> bool {
+        self.mpris.get().is_some()
+    }
+
+    pub fn set_status(&self, playing: bool, paused: bool) {
         let mpris = self.mpris.clone();
 
+        if self.playing.replace(playing) != playing {
+            self.update_controls();
+        }
+
+        let status = match (playing, paused) {
+            (false, _) => PlaybackStatus::Stopped,
+            (true, true) => PlaybackStatus::Paused,
+            (true, false) => PlaybackStatus::Playing,
+        };
+
         spawn_local!(async move {
-            if let Some(mpris) = mpris.get() {
-                let status = match paused {
-                    true => PlaybackStatus::Paused,
-                    false => PlaybackStatus::Playing,
-                };
-
-                if let Err(e) = mpris.set_playback_status(status).await {
-                    error!("Failed to set mpris playback status: {e}");
-                }
+            if let Some(mpris) = mpris.get()
+                && mpris.playback_status() != status
+                && let Err(e) = mpris.set_playback_status(status).await
+            {
+                error!("Failed to set mpris playback status: {e}");
             }
         });
     }
 
-    pub fn set_metadata(&self, title: String, artist: Option<String>, art_url: Option<String>) {
+    pub fn set_metadata(&self, media: MediaMetadata) {
+        let length = match media.duration {
+            Some(duration) if duration > 0.0 => Some(from_seconds(duration)),
+            _ => self.metadata.borrow().length(),
+        };
+
+        let mut metadata = Metadata::new();
+        metadata.set_trackid(
+            track_id(media.id.as_deref().unwrap_or(&media.title))
+                .or_else(|| self.file_track_id.borrow().clone()),
+        );
+        metadata.set_title(Some(media.title));
+        metadata.set_artist(Some(media.artist.map_or(vec![], |artist| vec![artist])));
+        metadata.set_art_url(media.art_url);
+        metadata.set_album(media.series);
+        metadata.set_track_number(media.episode);
+        metadata.set_url(media.url);
+        metadata.set_length(length);
+
+        *self.metadata.borrow_mut() = metadata;
+        self.update_metadata();
+        self.update_controls();
+    }
+
+    /// Gives a newly loaded file a track id, so it can be seeked before metadata arrives.
+    pub fn start_file(&self, playlist_entry_id: i64) {
+        let file_track_id = TrackId::try_from(
+            format!(
+                "{FILE_TRACK_ID_PREFIX}/{}",
+                playlist_entry_id.unsigned_abs()
+            )
+            .as_str(),
+        )
+        .inspect_err(|e| error!("Invalid mpris track id: {e}"))
+        .ok();
+
+        self.file_track_id.replace(file_track_id.clone());
+
+        if self.metadata.borrow().trackid().is_none() {
+            self.metadata.borrow_mut().set_trackid(file_track_id);
+            self.update_metadata();
+        }
+    }
+
+    pub fn set_length(&self, seconds: f64) {
+        let length = (seconds > 0.0).then(|| from_seconds(seconds));
+        if self.metadata.borrow().length() == length {
+            return;
+        }
+
+        self.metadata.borrow_mut().set_length(length);
+        self.update_metadata();
+        self.update_controls();
+    }
+
+    /// Seeking needs a known length, next and previous only make sense for episodes.
+    fn update_controls(&self) {
         let mpris = self.mpris.clone();
 
+        let playing = self.playing.get();
+        let metadata = self.metadata.borrow();
+        let can_seek = playing && metadata.length().is_some();
+        let can_skip = playing && metadata.album().is_some();
+
         spawn_local!(async move {
-            if let Some(mpris) = mpris.get() {
-                let mut metadata = Metadata::new();
-                metadata.set_title(Some(title));
-                metadata.set_artist(Some(artist.map_or(vec![], |artist| vec![artist])));
-                metadata.set_art_url(art_url);
-
-                if let Err(e) = mpris.set_metadata(metadata).await {
-                    error!("Failed to set mpris metadata: {e}");
-                }
+            let Some(mpris) = mpris.get() else {
+                return;
+            };
+
+            if mpris.can_seek() != can_seek
+                && let Err(e) = mpris.set_can_seek(can_seek).await
+            {
+                error!("Failed to set mpris can seek: {e}");
+            }
+
+            if mpris.can_go_next() != can_skip
+                && let Err(e) = mpris.set_can_go_next(can_skip).await
+            {
+                error!("Failed to set mpris can go next: {e}");
+            }
+
+            if mpris.can_go_previous() != can_skip
+                && let Err(e) = mpris.set_can_go_previous(can_skip).await
+            {
+                error!("Failed to set mpris can go previous: {e}");
             }
         });
     }
 
-    pub fn set_status_callback<F: Fn(bool) + 'static>(&self, callback: F) {
-        self.status_callback
+    fn update_metadata(&self) {
+        let mpris = self.mpris.clone();
+        let metadata = self.metadata.borrow().clone();
+
+        spawn_local!(async move {
+            if let Some(mpris) = mpris.get()
+                && let Err(e) = mpris.set_metadata(metadata).await
+            {
+                error!("Failed to set mpris metadata: {e}");
+            }
+        });
+    }
+
+    /// Position is polled by clients, so this does not emit any signal.
+    pub fn set_position(&self, seconds: f64) {
+        if let Some(mpris) = self.mpris.get() {
+            mpris.set_position(from_seconds(seconds));
+        }
+    }
+
+    pub fn seeked(&self, seconds: f64) {
+        let mpris = self.mpris.clone();
+
+        spawn_local!(async move {
+            if let Some(mpris) = mpris.get()
+                && let Err(e) = mpris.seeked(from_seconds(seconds)).await
+            {
+                error!("Failed to emit mpris seeked: {e}");
+            }
+        });
+    }
+
+    pub fn set_volume(&self, volume: f64) {
+        let mpris = self.mpris.clone();
+
+        spawn_local!(async move {
+            if let Some(mpris) = mpris.get()
+                && let Err(e) = mpris.set_volume(volume / MAXIMUM_VOLUME).await
+            {
+                error!("Failed to set mpris volume: {e}");
+            }
+        });
+    }
+
+    pub fn set_rate(&self, rate: f64) {
+        let mpris = self.mpris.clone();
+
+        spawn_local!(async move {
+            if let Some(mpris) = mpris.get()
+                && let Err(e) = mpris.set_rate(rate).await
+            {
+                error!("Failed to set mpris rate: {e}");
+            }
+        });
+    }
+
+    pub fn set_play_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.play_callback.borrow_mut().replace(Box::new(callback));
+    }
+
+    pub fn set_pause_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.pause_callback.borrow_mut().replace(Box::new(callback));
+    }
+
+    pub fn set_play_pause_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.play_pause_callback
             .borrow_mut()
             .replace(Box::new(callback));
     }
 
+    pub fn set_stop_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.stop_callback.borrow_mut().replace(Box::new(callback));
+    }
+
     pub fn set_raise_callback<F: Fn() + 'static>(&self, callback: F) {
         self.raise_callback.borrow_mut().replace(Box::new(callback));
     }
+
+    pub fn set_next_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.next_callback.borrow_mut().replace(Box::new(callback));
+    }
+
+    pub fn set_previous_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.previous_callback
+            .borrow_mut()
+            .replace(Box::new(callback));
+    }
+
+    pub fn set_seek_callback<F: Fn(f64, bool) + 'static>(&self, callback: F) {
+        self.seek_callback.borrow_mut().replace(Box::new(callback));
+    }
+
+    pub fn set_volume_callback<F: Fn(f64) + 'static>(&self, callback: F) {
+        self.volume_callback
+            .borrow_mut()
+            .replace(Box::new(callback));
+    }
+
+    pub fn set_probed_callback<F: Fn() + 'static>(&self, callback: F) {
+        self.probed_callback
+            .borrow_mut()
+            .replace(Box::new(callback));
+    }
+
+    pub fn set_rate_callback<F: Fn(f64) + 'static>(&self, callback: F) {
+        self.rate_callback.borrow_mut().replace(Box::new(callback));
+    }
 }
 
 impl ObjectImpl for Mpris {}
+
+/// Track ids must be valid object paths, so anything but ascii alphanumerics is escaped.
+///
+/// Empty ids would leave an empty path element and give `None`.
+fn track_id(id: &str) -> Option<TrackId> {
+    if id.is_empty() {
+        return None;
+    }
+
+    let element = id
+        .bytes()
+        .map(|byte| match byte.is_ascii_alphanumeric() {
+            true => (byte as char).to_string(),
+            false => format!("_{byte:02x}"),
+        })
+        .collect::<String>();
+
+    TrackId::try_from(format!("{TRACK_ID_PREFIX}/{element}").as_str())
+        .inspect_err(|e| error!("Invalid mpris track id: {e}"))
+        .ok()
+}
+
+fn to_seconds(time: Time) -> f64 {
+    time.as_micros() as f64 / 1_000_000.0
+}
+
+fn from_seconds(seconds: f64) -> Time {
+    Time::from_micros((seconds * 1_000_000.0) as i64)
+}
@@ -1,8 +1,27 @@
+mod config;
 mod imp;
 
 use gtk::glib::{self, subclass::prelude::*};
 
+#[derive(Debug, Default)]
+pub struct MediaMetadata {
+    /// Stable identifier of the item, used to derive the track id.
+    pub id: Option<String>,
+    pub title: String,
+    pub artist: Option<String>,
+    pub art_url: Option<String>,
+    pub series: Option<String>,
+    pub episode: Option<i32>,
+    pub url: Option<String>,
+    pub duration: Option<f64>,
+}
+
 glib::wrapper! {
+    /// Root and Player MPRIS interfaces backed by mpris-server's `Player`.
+    ///
+    /// Only the Root and Player interfaces are exposed, `HasTrackList` is false.
+    /// A TrackList of upcoming episodes would need a port to `LocalServer` with our own
+    /// interface impls and an ipc payload for the episodes, which is out of scope here.
     pub struct Mpris(ObjectSubclass<imp::Mpris>);
 }
 
@@ -17,19 +36,87 @@ impl Mpris {
         self.imp().start(id, name);
     }
 
-    pub fn set_status(&self, paused: bool) {
-        self.imp().set_status(paused);
+    pub fn is_available(&self) -> bool {
+        self.imp().is_available()
+    }
+
+    /// Reports Stopped when nothing is playing, otherwise Playing or Paused.
+    pub fn set_status(&self, playing: bool, paused: bool) {
+        self.imp().set_status(playing, paused);
+    }
+
+    pub fn set_metadata(&self, metadata: MediaMetadata) {
+        self.imp().set_metadata(metadata);
+    }
+
+    pub fn set_position(&self, seconds: f64) {
+        self.imp().set_position(seconds);
+    }
+
+    pub fn seeked(&self, seconds: f64) {
+        self.imp().seeked(seconds);
+    }
+
+    pub fn start_file(&self, playlist_entry_id: i64) {
+        self.imp().start_file(playlist_entry_id);
+    }
+
+    pub fn set_length(&self, seconds: f64) {
+        self.imp().set_length(seconds);
+    }
+
+    pub fn set_volume(&self, volume: f64) {
+        self.imp().set_volume(volume);
+    }
+
+    pub fn set_rate(&self, rate: f64) {
+        self.imp().set_rate(rate);
+    }
+
+    pub fn connect_play<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_play_callback(callback);
+    }
+
+    pub fn connect_pause<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_pause_callback(callback);
     }
 
-    pub fn set_metadata(&self, title: String, artist: Option<String>, art_url: Option<String>) {
-        self.imp().set_metadata(title, artist, art_url);
+    pub fn connect_play_pause<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_play_pause_callback(callback);
     }
 
-    pub fn connect_status<F: Fn(bool) + 'static>(&self, callback: F) {
-        self.imp().set_status_callback(callback);
+    pub fn connect_stop<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_stop_callback(callback);
     }
 
     pub fn connect_raise<F: Fn() + 'static>(&self, callback: F) {
         self.imp().set_raise_callback(callback);
     }
+
+    pub fn connect_next<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_next_callback(callback);
+    }
+
+    pub fn connect_previous<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_previous_callback(callback);
+    }
+
+    /// Called with a position in seconds, and whether it is absolute or an offset.
+    pub fn connect_seek<F: Fn(f64, bool) + 'static>(&self, callback: F) {
+        self.imp().set_seek_callback(callback);
+    }
+
+    /// Called with a volume in percent, as mpv expects it.
+    pub fn connect_volume<F: Fn(f64) + 'static>(&self, callback: F) {
+        self.imp().set_volume_callback(callback);
+    }
+
+    /// Called once the server was either started or failed to.
+    pub fn connect_probed<F: Fn() + 'static>(&self, callback: F) {
+        self.imp().set_probed_callback(callback);
+    }
+
+    pub fn connect_rate<F: Fn(f64) + 'static>(&self, callback: F) {
+        self.imp().set_rate_callback(callback);
+    }
 }
@@ -0,0 +1,86 @@
+use std::{cell::Cell, sync::OnceLock};
+
+use adw::subclass::prelude::*;
+use gtk::{
+    glib::{self, Properties, subclass::InitializingObject, subclass::Signal},
+    prelude::*,
+};
+
+#[derive(Default, Properties, gtk::CompositeTemplate)]
+#[properties(wrapper_type = super::PipWindow)]
+#[template(file = "pip.ui")]
+pub struct PipWindow {
+    #[property(get, set = Self::set_paused)]
+    paused: Cell<bool>,
+    #[template_child]
+    pub overlay: TemplateChild<gtk::Overlay>,
+    #[template_child]
+    play_pause: TemplateChild<gtk::Button>,
+}
+
+impl PipWindow {
+    fn set_paused(&self, paused: bool) {
+        self.paused.set(paused);
+
+        let icon_name = match paused {
+            true => "media-playback-start-symbolic",
+            false => "media-playback-pause-symbolic",
+        };
+
+        self.play_pause.set_icon_name(icon_name);
+    }
+}
+
+#[gtk::template_callbacks]
+impl PipWindow {
+    #[template_callback]
+    fn on_play_pause_clicked(&self) {
+        self.obj().emit_by_name::<()>("play-pause", &[]);
+    }
+
+    #[template_callback]
+    fn on_leave_clicked(&self) {
+        self.obj().emit_by_name::<()>("leave", &[]);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PipWindow {
+    const NAME: &'static str = "PipWindow";
+    type Type = super::PipWindow;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.bind_template_callbacks();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for PipWindow {
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+        SIGNALS.get_or_init(|| {
+            vec![
+                Signal::builder("play-pause").build(),
+                Signal::builder("leave").build(),
+            ]
+        })
+    }
+}
+
+impl WidgetImpl for PipWindow {}
+
+impl WindowImpl for PipWindow {
+    fn close_request(&self) -> glib::Propagation {
+        self.obj().emit_by_name::<()>("leave", &[]);
+
+        glib::Propagation::Stop
+    }
+}
+
+impl AdwWindowImpl for PipWindow {}
@@ -0,0 +1,55 @@
+mod imp;
+
+use adw::subclass::prelude::*;
+use gtk::{
+    Widget,
+    glib::{self, closure_local, object::IsA, prelude::*},
+};
+
+use crate::app::Application;
+
+glib::wrapper! {
+    /// Small window showing the video while the main window is hidden.
+    ///
+    /// GTK 4 has no api to keep a window above others and Wayland has no protocol for it,
+    /// so staying on top is left to the compositor, e.g. a KWin window rule.
+    pub struct PipWindow(ObjectSubclass<imp::PipWindow>)
+    @extends adw::Window, gtk::Window, gtk::Widget,
+    @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager, gtk::Native, gtk::Root;
+}
+
+impl PipWindow {
+    pub fn new(application: &Application) -> Self {
+        glib::Object::builder()
+            .property("application", application)
+            .build()
+    }
+
+    pub fn set_video(&self, widget: &impl IsA<Widget>) {
+        self.imp().overlay.set_child(Some(widget));
+    }
+
+    pub fn clear_video(&self) {
+        self.imp().overlay.set_child(None::<&Widget>);
+    }
+
+    pub fn connect_play_pause<T: Fn() + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "play-pause",
+            false,
+            closure_local!(move |_: PipWindow| {
+                callback();
+            }),
+        );
+    }
+
+    pub fn connect_leave<T: Fn() + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "leave",
+            false,
+            closure_local!(move |_: PipWindow| {
+                callback();
+            }),
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use std::sync::{Arc, LazyLock, OnceLock};
+use std::sync::{
+    Arc, LazyLock, OnceLock,
+    atomic::{AtomicBool, Ordering},
+};
 
 use flume::{Sender, unbounded};
 use gettextrs::gettext;
@@ -21,9 +24,14 @@ use crate::{
 #[derive(Default)]
 pub struct Tray {
     handle: Arc<Mutex<Option<Handle<TrayIcon>>>>,
+    available: Arc<AtomicBool>,
 }
 
 impl Tray {
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::SeqCst)
+    }
+
     pub fn update(&self, state: bool) {
         let local_handle = self.handle.clone();
         tokio::spawn(async move {
@@ -50,6 +58,7 @@ impl ObjectImpl for Tray {
                 Signal::builder("show").build(),
                 Signal::builder("hide").build(),
                 Signal::builder("quit").build(),
+                Signal::builder("probed").build(),
             ]
         })
     }
@@ -59,12 +68,14 @@ impl ObjectImpl for Tray {
 
         let (sender, receiver) = unbounded::<TrayEvent>();
 
+        let probe_sender = sender.clone();
         let tray_icon = TrayIcon {
             sender,
             window_visible: true,
         };
 
         let local_handle = self.handle.clone();
+        let available = self.available.clone();
         tokio::spawn(async move {
             let mut handle_guard = local_handle.lock().await;
             *handle_guard = tray_icon
@@ -74,7 +85,10 @@ impl ObjectImpl for Tray {
                 .inspect_err(|err| {
                     error!("Failed to create tray icon: {err}");
                 })
-                .ok()
+                .ok();
+
+            available.store(handle_guard.is_some(), Ordering::SeqCst);
+            probe_sender.send(TrayEvent::Probed).ok();
         });
 
         spawn_local!(clone!(
@@ -86,6 +100,7 @@ impl ObjectImpl for Tray {
                         TrayEvent::Show => object.emit_by_name::<()>("show", &[]),
                         TrayEvent::Hide => object.emit_by_name::<()>("hide", &[]),
                         TrayEvent::Quit => object.emit_by_name::<()>("quit", &[]),
+                        TrayEvent::Probed => object.emit_by_name::<()>("probed", &[]),
                     }
                 }
             }
@@ -98,6 +113,7 @@ pub enum TrayEvent {
     Show,
     Hide,
     Quit,
+    Probed,
 }
 
 pub struct TrayIcon {
@@ -15,6 +15,10 @@ impl Default for Tray {
 }
 
 impl Tray {
+    pub fn is_available(&self) -> bool {
+        self.imp().is_available()
+    }
+
     pub fn update(&self, state: bool) {
         self.imp().update(state);
     }
@@ -39,6 +43,17 @@ impl Tray {
         );
     }
 
+    /// Called once the tray icon was either spawned or failed to.
+    pub fn connect_probed<T: Fn() + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "probed",
+            false,
+            closure_local!(move |_: Tray| {
+                callback();
+            }),
+        );
+    }
+
     pub fn connect_quit<T: Fn() + 'static>(&self, callback: T) {
         self.connect_closure(
             "quit",
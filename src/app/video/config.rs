@@ -1,42 +1,14 @@
-pub const FLOAT_PROPERTIES: &[&str] = &[
-    "time-pos",
-    "duration",
-    "volume",
-    "speed",
-    "sub-pos",
-    "sub-scale",
-    "sub-delay",
-    "cache-buffering-state",
-    "demuxer-cache-time",
-    "panscan",
-];
-
-pub const BOOL_PROPERTIES: &[&str] = &[
-    "pause",
-    "buffering",
-    "seeking",
-    "osc",
-    "input-default-bindings",
-    "input-vo-keyboard",
-    "eof-reached",
-    "paused-for-cache",
-    "keepaspect",
-];
-
-pub const STRING_PROPERTIES: &[&str] = &[
-    "path",
-    "mpv-version",
-    "ffmpeg-version",
-    "hwdec",
-    "track-list",
-    "sub-color",
-    "sub-back-color",
-    "sub-border-color",
-    "sid",
-    "aid",
-    "vid",
-    "mute",
-    "metadata",
-    "video-params",
-    "sub-ass-override",
-];
+pub const WEB_OBSERVER: u64 = 0;
+pub const SHELL_OBSERVER: u64 = 1;
+/// Commands the web UI can send, anything else is rejected, prefixes like `async` included.
+pub const ALLOWED_COMMANDS: &[&str] =
+    &["loadfile", "sub-add", "seek", "stop", "set", "add", "cycle"];
+/// Commands that change a property by name, so they go through the property policy.
+pub const PROPERTY_COMMANDS: &[&str] = &["set", "add", "cycle"];
+/// Url and flags of `loadfile`, per file options could set denied properties.
+pub const LOADFILE_MAX_ARGS: usize = 2;
+pub const CONFIG_DIR: &str = "mpv";
+pub const CONFIG_FILE: &str = "mpv.conf";
+pub const INPUT_CONFIG_FILE: &str = "input.conf";
+pub const SCRIPTS_DIR: &str = "scripts";
+pub const SCREENSHOTS_DIR: &str = "Stremio";
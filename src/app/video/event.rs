@@ -0,0 +1,115 @@
+use std::ffi::{CStr, c_int};
+
+use libmpv2_sys::{
+    mpv_end_file_reason, mpv_error_string, mpv_event_command, mpv_event_end_file, mpv_event_id,
+    mpv_event_log_message, mpv_event_property, mpv_event_start_file, mpv_format, mpv_handle,
+    mpv_node, mpv_wait_event,
+};
+use serde_json::{Value, json};
+use tracing::warn;
+
+use super::{log, node};
+
+#[derive(Debug)]
+pub enum MpvEvent {
+    PropertyChange(u64, String, Value),
+    EndFile(&'static str, Option<String>),
+    CommandReply(u64, Result<Value, String>),
+    /// Playback events forwarded as is, named like `mpv-event-<name>` over ipc.
+    Playback(&'static str, Value),
+    /// Verbosity of the message and its json form.
+    LogMessage(usize, Value),
+}
+
+/// Returns the next event handled by the shell, or `None` once the queue is empty or mpv shut down.
+///
+/// Event data is converted right away since mpv frees it on the next call.
+pub fn poll(handle: *mut mpv_handle) -> Option<MpvEvent> {
+    loop {
+        let event = unsafe { mpv_wait_event(handle, 0.0).as_ref() }?;
+
+        match event.event_id {
+            mpv_event_id::None => return None,
+            // mpv keeps returning this once the core quit, so it has to end the drain
+            mpv_event_id::Shutdown => {
+                warn!("Mpv core was shut down");
+                return None;
+            }
+            mpv_event_id::PropertyChange => {
+                let property = unsafe { &*(event.data as *const mpv_event_property) };
+                let name = unsafe { CStr::from_ptr(property.name) }
+                    .to_string_lossy()
+                    .into_owned();
+
+                let value = match property.format {
+                    mpv_format::Node => unsafe {
+                        node::to_value(&*(property.data as *const mpv_node))
+                    },
+                    _ => Value::Null,
+                };
+
+                return Some(MpvEvent::PropertyChange(event.reply_userdata, name, value));
+            }
+            mpv_event_id::EndFile => {
+                let end_file = unsafe { &*(event.data as *const mpv_event_end_file) };
+                let reason = match end_file.reason {
+                    mpv_end_file_reason::Eof => "eof",
+                    mpv_end_file_reason::Stop => "stop",
+                    mpv_end_file_reason::Quit => "quit",
+                    mpv_end_file_reason::Error => "error",
+                    mpv_end_file_reason::Redirect => "redirect",
+                    _ => "other",
+                };
+
+                let error = (end_file.reason == mpv_end_file_reason::Error)
+                    .then(|| error_string(end_file.error));
+
+                return Some(MpvEvent::EndFile(reason, error));
+            }
+            mpv_event_id::CommandReply => {
+                let result = match event.error {
+                    0.. => {
+                        let command = unsafe { &*(event.data as *const mpv_event_command) };
+                        Ok(unsafe { node::to_value(&command.result) })
+                    }
+                    code => Err(error_string(code)),
+                };
+
+                return Some(MpvEvent::CommandReply(event.reply_userdata, result));
+            }
+            mpv_event_id::StartFile => {
+                let start_file = unsafe { &*(event.data as *const mpv_event_start_file) };
+                let data = json!({ "playlistEntryId": start_file.playlist_entry_id });
+
+                return Some(MpvEvent::Playback("start-file", data));
+            }
+            mpv_event_id::FileLoaded => {
+                return Some(MpvEvent::Playback("file-loaded", Value::Null));
+            }
+            mpv_event_id::Seek => return Some(MpvEvent::Playback("seek", Value::Null)),
+            mpv_event_id::PlaybackRestart => {
+                return Some(MpvEvent::Playback("playback-restart", Value::Null));
+            }
+            mpv_event_id::VideoReconfig => {
+                return Some(MpvEvent::Playback("video-reconfig", Value::Null));
+            }
+            mpv_event_id::AudioReconfig => {
+                return Some(MpvEvent::Playback("audio-reconfig", Value::Null));
+            }
+            mpv_event_id::LogMessage => {
+                let message = unsafe { &*(event.data as *const mpv_event_log_message) };
+                log::forward(message);
+
+                let (verbosity, data) = log::to_value(message);
+                return Some(MpvEvent::LogMessage(verbosity, data));
+            }
+            _ => continue,
+        }
+    }
+}
+
+pub fn error_string(code: c_int) -> String {
+    unsafe { CStr::from_ptr(mpv_error_string(code)) }
+        .to_string_lossy()
+        .into_owned()
+}
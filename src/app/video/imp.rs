@@ -1,84 +1,335 @@
+use flume::Sender;
 use gdk_wayland::{WaylandDisplay, wayland_client::Proxy};
 use gtk::{
     gdk::GLContext,
-    glib::{self, ControlFlow, Propagation, Properties, Variant, clone, subclass::Signal},
+    gio::{Settings, prelude::SettingsExt},
+    glib::{self, Propagation, Properties, subclass::Signal},
     prelude::*,
     subclass::prelude::*,
 };
 use libmpv2::{
-    Format, Mpv, SetData,
-    events::{Event, PropertyData},
-    mpv_end_file_reason,
+    GetData, Mpv,
     render::{OpenGLInitParams, RenderContext, RenderParam, RenderParamApiType},
 };
-use std::{cell::RefCell, env, os::raw::c_void, sync::OnceLock};
-use tracing::error;
+use libmpv2_sys::{
+    mpv_command_node, mpv_command_node_async, mpv_format, mpv_observe_property, mpv_set_property,
+    mpv_set_wakeup_callback,
+};
+use serde_json::{Value, json};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::CString,
+    os::raw::c_void,
+    ptr,
+    sync::OnceLock,
+};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+use crate::{app::config::APP_ID, spawn_local};
+
+use super::{
+    config::SHELL_OBSERVER,
+    event::{self, MpvEvent},
+    log,
+    node::OwnedNode,
+};
+
+/// Stores a value, returning whether it changed.
+fn update<T: PartialEq + Copy>(cell: &Cell<T>, value: T) -> bool {
+    cell.replace(value) != value
+}
 
 fn get_proc_address(_context: &GLContext, name: &str) -> *mut c_void {
     epoxy::get_proc_addr(name) as _
 }
 
+/// Called by mpv from any thread when its event queue becomes non-empty.
+unsafe extern "C" fn on_wakeup(data: *mut c_void) {
+    let sender = unsafe { &*(data as *const Sender<()>) };
+    sender.send(()).ok();
+}
+
 #[derive(Properties)]
 #[properties(wrapper_type = super::Video)]
 pub struct Video {
+    #[property(get)]
+    playing: Cell<bool>,
+    #[property(get)]
+    paused: Cell<bool>,
+    #[property(get)]
+    time_pos: Cell<f64>,
+    #[property(get)]
+    duration: Cell<f64>,
+    #[property(get)]
+    volume: Cell<f64>,
+    #[property(get)]
+    speed: Cell<f64>,
+    seeking: Cell<bool>,
     mpv: RefCell<Mpv>,
     render_context: RefCell<Option<RenderContext>>,
+    /// Video track disabled while the widget is unrealized, moving it to another window does that.
+    suspended_vid: RefCell<Option<Value>>,
+    hwdec: String,
+    /// Verbosity of the log messages forwarded to the web UI.
+    log_verbosity: usize,
+    property_allowlist: Vec<String>,
+    property_denylist: Vec<String>,
+    commands: RefCell<HashMap<u64, oneshot::Sender<Result<Value, String>>>>,
+    next_command: Cell<u64>,
+    wakeup: RefCell<Option<Box<Sender<()>>>>,
 }
 
 impl Default for Video {
     fn default() -> Self {
-        let log = env::var("RUST_LOG");
-        let msg_level = match log {
-            Ok(scope) => &format!("all={}", scope.as_str()),
-            _ => "all=no",
-        };
+        let settings = Settings::new(APP_ID);
+        let hwdec = settings.string("hwdec").to_string();
+        let log_level = settings.string("mpv-log-level");
 
         let mpv = Mpv::with_initializer(|init| {
             init.set_property("vo", "libmpv")?;
             init.set_property("video-timing-offset", "0")?;
             init.set_property("video-sync", "audio")?;
-            init.set_property("terminal", "yes")?;
-            init.set_property("msg-level", msg_level)?;
+            init.set_property("terminal", "no")?;
+            init.set_property("hwdec", hwdec.as_str())?;
             Ok(())
         })
         .expect("Failed to create mpv");
 
         mpv.disable_deprecated_events().ok();
+        log::request(mpv.ctx.as_ptr(), &log_level);
+
+        let strings = |key: &str| {
+            settings
+                .strv(key)
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        };
 
         Self {
+            playing: Default::default(),
+            paused: Default::default(),
+            time_pos: Default::default(),
+            duration: Default::default(),
+            volume: Cell::new(100.0),
+            speed: Cell::new(1.0),
+            seeking: Default::default(),
             mpv: RefCell::new(mpv),
             render_context: Default::default(),
+            suspended_vid: Default::default(),
+            hwdec,
+            log_verbosity: log::verbosity(&log_level),
+            property_allowlist: strings("mpv-property-allowlist"),
+            property_denylist: strings("mpv-property-denylist"),
+            commands: Default::default(),
+            next_command: Cell::new(1),
+            wakeup: Default::default(),
         }
     }
 }
 
 impl Video {
-    fn on_event<T: Fn(Event)>(&self, callback: T) {
-        if let Some(result) = self.mpv.borrow_mut().wait_event(0.0) {
-            match result {
-                Ok(event) => callback(event),
-                Err(e) => error!("Failed to wait for event: {e}"),
+    /// Handles every queued event, mpv only wakes us up again once the queue was emptied.
+    fn drain_events(&self) {
+        let object = self.obj();
+        let handle = self.mpv.borrow().ctx.as_ptr();
+
+        while let Some(event) = event::poll(handle) {
+            match event {
+                MpvEvent::PropertyChange(SHELL_OBSERVER, name, value) => {
+                    self.on_shell_property_change(&name, value);
+                }
+                MpvEvent::PropertyChange(_, name, value) => {
+                    let value = value.to_string();
+                    object.emit_by_name::<()>("property-changed", &[&name, &value]);
+                }
+                MpvEvent::EndFile(reason, error) => {
+                    object.emit_by_name::<()>("playback-ended", &[&reason, &error]);
+                }
+                MpvEvent::Playback(name, data) => {
+                    match name {
+                        "seek" => self.seeking.set(true),
+                        "playback-restart" if self.seeking.replace(false) => {
+                            object.emit_by_name::<()>("seeked", &[]);
+                        }
+                        _ => {}
+                    }
+
+                    let data = data.to_string();
+                    object.emit_by_name::<()>("playback-event", &[&name, &data]);
+                }
+                MpvEvent::LogMessage(verbosity, data) => {
+                    if (1..=self.log_verbosity).contains(&verbosity) {
+                        let data = data.to_string();
+                        object.emit_by_name::<()>("playback-event", &[&"log-message", &data]);
+                    }
+                }
+                MpvEvent::CommandReply(id, result) => {
+                    self.on_command_reply(id, result);
+                }
             }
-        };
+        }
     }
 
-    pub fn send_command(&self, name: &str, args: &[&str]) {
-        if let Err(e) = self.mpv.borrow().command(name, args) {
-            error!("Failed to send command {name}: {e}");
+    pub fn command(&self, args: &[&str]) -> Result<(), String> {
+        let mut node = OwnedNode::new(&json!(args));
+        let handle = self.mpv.borrow().ctx.as_ptr();
+        let code = unsafe { mpv_command_node(handle, node.as_mut_ptr(), ptr::null_mut()) };
+
+        match code {
+            0.. => Ok(()),
+            code => Err(event::error_string(code)),
         }
     }
 
-    pub fn observe_property(&self, name: &str, format: Format) {
-        if let Err(e) = self.mpv.borrow().observe_property(name, format, 0) {
-            error!("Failed to observe property {name}: {e}");
+    /// Properties observed by the shell itself, the web UI observes its own separately.
+    fn on_shell_property_change(&self, name: &str, value: Value) {
+        match name {
+            "hwdec-current" => {
+                let Some(current) = value.as_str() else {
+                    return;
+                };
+
+                let requested = self.hwdec.as_str();
+                if current == "no" && requested != "no" {
+                    warn!(
+                        "Hardware decoding ({requested}) is not available, falling back to software"
+                    );
+                } else {
+                    info!("Hardware decoding: {current}");
+                }
+
+                let data = json!({ "requested": requested, "current": current }).to_string();
+                self.obj()
+                    .emit_by_name::<()>("playback-event", &[&"hwdec-changed", &data]);
+            }
+            "pause" => {
+                if update(&self.paused, value.as_bool().unwrap_or_default()) {
+                    self.obj().notify_paused();
+                }
+            }
+            "time-pos" => {
+                if update(&self.time_pos, value.as_f64().unwrap_or_default()) {
+                    self.obj().notify_time_pos();
+                }
+            }
+            "duration" => {
+                if update(&self.duration, value.as_f64().unwrap_or_default()) {
+                    self.obj().notify_duration();
+                }
+            }
+            "volume" => {
+                if let Some(volume) = value.as_f64()
+                    && update(&self.volume, volume)
+                {
+                    self.obj().notify_volume();
+                }
+            }
+            "speed" => {
+                if let Some(speed) = value.as_f64()
+                    && update(&self.speed, speed)
+                {
+                    self.obj().notify_speed();
+                }
+            }
+            "idle-active" => {
+                let playing = value.as_bool().is_some_and(|idle| !idle);
+                if self.playing.replace(playing) != playing {
+                    self.obj().notify_playing();
+                }
+            }
+            _ => {}
         }
     }
 
-    pub fn set_property<T: SetData>(&self, name: &str, value: T) {
-        if let Err(e) = self.mpv.borrow().set_property(name, value) {
-            error!("Failed to set property {name}: {e}");
+    /// Properties must be in the allowlist, and the denylist is checked on top of it.
+    pub fn is_property_allowed(&self, name: &str) -> bool {
+        let allowed = self
+            .property_allowlist
+            .iter()
+            .any(|allowed| allowed == name);
+        let denied = self.property_denylist.iter().any(|denied| denied == name);
+
+        allowed && !denied
+    }
+
+    /// Runs a command without blocking, the receiver gets its result once mpv replies.
+    pub fn command_async(&self, command: &Value) -> oneshot::Receiver<Result<Value, String>> {
+        let (sender, receiver) = oneshot::channel();
+
+        let id = self.next_command.get();
+        self.next_command.set(id.wrapping_add(1));
+
+        let mut node = OwnedNode::new(command);
+        let handle = self.mpv.borrow().ctx.as_ptr();
+        let code = unsafe { mpv_command_node_async(handle, id, node.as_mut_ptr()) };
+
+        match code {
+            0.. => {
+                self.commands.borrow_mut().insert(id, sender);
+            }
+            code => {
+                sender.send(Err(event::error_string(code))).ok();
+            }
+        }
+
+        receiver
+    }
+
+    fn on_command_reply(&self, id: u64, result: Result<Value, String>) {
+        if let Some(sender) = self.commands.borrow_mut().remove(&id) {
+            sender.send(result).ok();
         }
     }
+
+    pub fn observe_property(&self, name: &str, observer: u64) -> Result<(), String> {
+        let c_name = CString::new(name).map_err(|e| e.to_string())?;
+
+        let handle = self.mpv.borrow().ctx.as_ptr();
+        let code =
+            unsafe { mpv_observe_property(handle, observer, c_name.as_ptr(), mpv_format::Node) };
+
+        if code < 0 {
+            let message = event::error_string(code);
+            error!("Failed to observe property {name}: {message}");
+            return Err(message);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_property<T: GetData>(&self, name: &str) -> Option<T> {
+        self.mpv
+            .borrow()
+            .get_property::<T>(name)
+            .inspect_err(|e| error!("Failed to get property {name}: {e}"))
+            .ok()
+    }
+
+    pub fn set_property(&self, name: &str, value: &Value) -> Result<(), String> {
+        let c_name = CString::new(name).map_err(|e| e.to_string())?;
+
+        let mut node = OwnedNode::new(value);
+        let handle = self.mpv.borrow().ctx.as_ptr();
+        let code = unsafe {
+            mpv_set_property(
+                handle,
+                c_name.as_ptr(),
+                mpv_format::Node,
+                node.as_mut_ptr() as *mut c_void,
+            )
+        };
+
+        if code < 0 {
+            let message = event::error_string(code);
+            error!("Failed to set property {name}: {message}");
+            return Err(message);
+        }
+
+        Ok(())
+    }
 }
 
 #[glib::object_subclass]
@@ -95,11 +346,15 @@ impl ObjectImpl for Video {
         SIGNALS.get_or_init(|| {
             vec![
                 Signal::builder("property-changed")
-                    .param_types([str::static_type(), Variant::static_type()])
+                    .param_types([str::static_type(), str::static_type()])
                     .build(),
                 Signal::builder("playback-ended")
-                    .param_types([str::static_type()])
+                    .param_types([str::static_type(), Option::<String>::static_type()])
+                    .build(),
+                Signal::builder("playback-event")
+                    .param_types([str::static_type(), str::static_type()])
                     .build(),
+                Signal::builder("seeked").build(),
             ]
         })
     }
@@ -107,45 +362,41 @@ impl ObjectImpl for Video {
     fn constructed(&self) {
         self.parent_constructed();
 
-        glib::idle_add_local(clone!(
-            #[weak(rename_to = video)]
-            self,
-            #[weak(rename_to = object)]
-            self.obj(),
-            #[upgrade_or]
-            ControlFlow::Break,
-            move || {
-                video.on_event(|event| match event {
-                    Event::PropertyChange { name, change, .. } => {
-                        let value = match change {
-                            PropertyData::Str(v) => Some(v.to_variant()),
-                            PropertyData::Flag(v) => Some(v.to_variant()),
-                            PropertyData::Double(v) => Some(v.to_variant()),
-                            _ => None,
-                        };
-
-                        if let Some(value) = value {
-                            object.emit_by_name::<()>("property-changed", &[&name, &value]);
-                        }
-                    }
-                    Event::EndFile(reason) => {
-                        let reason = match reason {
-                            mpv_end_file_reason::Eof => "eof".to_string(),
-                            mpv_end_file_reason::Stop => "stop".to_string(),
-                            mpv_end_file_reason::Redirect => "redirect".to_string(),
-                            mpv_end_file_reason::Error => "error".to_string(),
-                            mpv_end_file_reason::Quit => "quit".to_string(),
-                            _ => "other".to_string(),
-                        };
-
-                        object.emit_by_name::<()>("playback-ended", &[&reason]);
-                    }
-                    _ => {}
-                });
+        let (sender, receiver) = flume::unbounded::<()>();
+        let sender = Box::new(sender);
+
+        let handle = self.mpv.borrow().ctx.as_ptr();
+        let data = &*sender as *const Sender<()> as *mut c_void;
+        unsafe { mpv_set_wakeup_callback(handle, Some(on_wakeup), data) };
+
+        *self.wakeup.borrow_mut() = Some(sender);
 
-                ControlFlow::Continue
+        self.observe_property("hwdec-current", SHELL_OBSERVER).ok();
+        self.observe_property("idle-active", SHELL_OBSERVER).ok();
+        self.observe_property("pause", SHELL_OBSERVER).ok();
+        self.observe_property("time-pos", SHELL_OBSERVER).ok();
+        self.observe_property("duration", SHELL_OBSERVER).ok();
+        self.observe_property("volume", SHELL_OBSERVER).ok();
+        self.observe_property("speed", SHELL_OBSERVER).ok();
+
+        let object = self.obj().downgrade();
+        spawn_local!(async move {
+            while receiver.recv_async().await.is_ok() {
+                let Some(object) = object.upgrade() else {
+                    break;
+                };
+
+                receiver.drain().for_each(drop);
+                object.imp().drain_events();
             }
-        ));
+        });
+    }
+
+    fn dispose(&self) {
+        let handle = self.mpv.borrow().ctx.as_ptr();
+        unsafe { mpv_set_wakeup_callback(handle, None, ptr::null_mut()) };
+
+        self.wakeup.take();
     }
 }
 
@@ -186,31 +437,40 @@ impl WidgetImpl for Video {
 
             let (sender, receiver) = flume::unbounded::<()>();
 
-            glib::idle_add_local(clone!(
-                #[weak]
-                object,
-                #[upgrade_or]
-                ControlFlow::Break,
-                move || {
-                    if let Ok(()) = receiver.try_recv() {
-                        object.queue_render();
-                    }
+            let object = object.downgrade();
+            spawn_local!(async move {
+                while receiver.recv_async().await.is_ok() {
+                    let Some(object) = object.upgrade() else {
+                        break;
+                    };
 
-                    ControlFlow::Continue
+                    receiver.drain().for_each(drop);
+                    object.queue_render();
                 }
-            ));
+            });
 
             render_context.set_update_callback(move || {
                 sender.send(()).ok();
             });
 
             *self.render_context.borrow_mut() = Some(render_context);
+            drop(mpv);
+
+            // Freeing the render context tore down the video output, reselecting the track restores it
+            if let Some(vid) = self.suspended_vid.take() {
+                self.set_property("vid", &vid).ok();
+            }
         }
     }
 
     fn unrealize(&self) {
         self.obj().make_current();
         if let Some(render_context) = self.render_context.borrow_mut().take() {
+            // mpv recommends disabling video before freeing the render context
+            let vid = self.get_property::<String>("vid").map(Value::String);
+            self.set_property("vid", &json!("no")).ok();
+            *self.suspended_vid.borrow_mut() = vid;
+
             drop(render_context);
         }
 
@@ -0,0 +1,87 @@
+use std::ffi::{CStr, CString};
+
+use libmpv2_sys::{mpv_event_log_message, mpv_handle, mpv_log_level, mpv_request_log_messages};
+use serde_json::{Value, json};
+use tracing::{debug, error, info, level_filters::LevelFilter, trace, warn};
+
+/// Mpv log levels from least to most verbose.
+const LEVELS: [&str; 8] = [
+    "no", "fatal", "error", "warn", "info", "v", "debug", "trace",
+];
+
+/// Emits a tracing event, targets and levels have to be known at compile time.
+macro_rules! emit {
+    ($target:expr, $level:expr, $text:expr) => {
+        match $level {
+            mpv_log_level::Fatal | mpv_log_level::Error => error!(target: $target, "{}", $text),
+            mpv_log_level::Warn => warn!(target: $target, "{}", $text),
+            mpv_log_level::Info => info!(target: $target, "{}", $text),
+            mpv_log_level::V | mpv_log_level::Debug => debug!(target: $target, "{}", $text),
+            _ => trace!(target: $target, "{}", $text),
+        }
+    };
+}
+
+/// Maps an mpv module to a `mpv::<module>` target, unknown modules use `mpv`.
+macro_rules! forward {
+    ($module:expr, $level:expr, $text:expr, [$($name:literal),* $(,)?]) => {
+        match $module {
+            $($name => emit!(concat!("mpv::", $name), $level, $text),)*
+            _ => emit!("mpv", $level, $text),
+        }
+    };
+}
+
+/// Asks mpv for the log messages the current tracing filter or the web UI can let through.
+pub fn request(handle: *mut mpv_handle, web_level: &str) {
+    let tracing_level = match LevelFilter::current() {
+        LevelFilter::OFF => "no",
+        LevelFilter::ERROR => "error",
+        LevelFilter::WARN => "warn",
+        LevelFilter::INFO => "info",
+        LevelFilter::DEBUG => "debug",
+        _ => "trace",
+    };
+
+    let level = match verbosity(web_level) > verbosity(tracing_level) {
+        true => web_level,
+        false => tracing_level,
+    };
+
+    let level = CString::new(level).expect("Failed to create log level");
+    unsafe { mpv_request_log_messages(handle, level.as_ptr()) };
+}
+
+/// Position of a level in [`LEVELS`], unknown levels count as disabled.
+pub fn verbosity(level: &str) -> usize {
+    LEVELS.iter().position(|known| *known == level).unwrap_or(0)
+}
+
+/// Converts a message to the `log-message` event of mpv's json ipc.
+pub fn to_value(message: &mpv_event_log_message) -> (usize, Value) {
+    let prefix = unsafe { CStr::from_ptr(message.prefix) }.to_string_lossy();
+    let level = unsafe { CStr::from_ptr(message.level) }.to_string_lossy();
+    let text = unsafe { CStr::from_ptr(message.text) }.to_string_lossy();
+
+    let data = json!({ "prefix": prefix, "level": level, "text": text });
+    (verbosity(&level), data)
+}
+
+pub fn forward(message: &mpv_event_log_message) {
+    let prefix = unsafe { CStr::from_ptr(message.prefix) }.to_string_lossy();
+    let text = unsafe { CStr::from_ptr(message.text) }.to_string_lossy();
+    let text = text.trim_end();
+
+    // Nested modules like `vo/gpu` are logged under their parent
+    let module = prefix.split('/').next().unwrap_or_default();
+
+    forward!(
+        module,
+        message.log_level,
+        text,
+        [
+            "ad", "af", "ao", "cache", "cplayer", "demux", "ffmpeg", "file", "global", "input",
+            "lavf", "libmpv", "osd", "stream", "sub", "vd", "vf", "vo",
+        ]
+    );
+}
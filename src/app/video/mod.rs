@@ -1,14 +1,54 @@
 mod config;
+mod event;
 mod imp;
+mod log;
+mod node;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use adw::subclass::prelude::ObjectSubclassIsExt;
-use gtk::glib::{self, Variant, closure_local, object::ObjectExt};
+use chrono::Local;
+use gtk::glib::{self, closure_local, object::ObjectExt};
 use itertools::Itertools;
-use libmpv2::Format;
-use serde_json::{Number, Value};
-use tracing::warn;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{error, info, warn};
+
+use crate::{app::config::APP_NAME, config::DATA_DIR};
+
+use config::{
+    ALLOWED_COMMANDS, CONFIG_DIR, CONFIG_FILE, INPUT_CONFIG_FILE, LOADFILE_MAX_ARGS,
+    PROPERTY_COMMANDS, SCREENSHOTS_DIR, SCRIPTS_DIR, WEB_OBSERVER,
+};
 
-use crate::app::video::config::{BOOL_PROPERTIES, FLOAT_PROPERTIES, STRING_PROPERTIES};
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl ScreenshotFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "png",
+            ScreenshotFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+/// Why the web UI could not observe or set an mpv property.
+#[derive(Debug)]
+pub enum PropertyError {
+    /// Rejected by the property allow/deny policy.
+    NotAllowed,
+    /// Refused by mpv, with its error string.
+    Mpv(String),
+}
 
 glib::wrapper! {
     pub struct Video(ObjectSubclass<imp::Video>)
@@ -30,87 +70,260 @@ impl Video {
         self.connect_closure(
             "property-changed",
             false,
-            closure_local!(move |_: Video, name: &str, value: Variant| {
-                match name {
-                    name if FLOAT_PROPERTIES.contains(&name) => {
-                        if let Some(value) = value.get::<f64>() {
-                            callback(name, Value::Number(Number::from_f64(value).unwrap()))
-                        }
-                    }
-                    name if BOOL_PROPERTIES.contains(&name) => {
-                        if let Some(value) = value.get::<bool>() {
-                            callback(name, Value::Bool(value))
-                        }
-                    }
-                    name if STRING_PROPERTIES.contains(&name) => {
-                        if let Some(value) = value.get::<String>() {
-                            if let Ok(json_value) = serde_json::from_str::<Value>(&value) {
-                                callback(name, json_value)
-                            } else {
-                                callback(name, Value::String(value))
-                            }
-                        }
-                    }
-                    _ => {}
-                };
+            closure_local!(move |_: Video, name: &str, value: &str| {
+                match serde_json::from_str::<Value>(value) {
+                    Ok(value) => callback(name, value),
+                    Err(e) => warn!("Failed to parse property {name}: {e}"),
+                }
             }),
         );
     }
 
-    pub fn connect_playback_ended<T: Fn(&str) + 'static>(&self, callback: T) {
+    pub fn connect_playback_ended<T: Fn(&str, Option<String>) + 'static>(&self, callback: T) {
         self.connect_closure(
             "playback-ended",
             false,
-            closure_local!(move |_: Video, reason: &str| {
-                callback(reason);
+            closure_local!(move |_: Video, reason: &str, error: Option<String>| {
+                callback(reason, error);
             }),
         );
     }
 
-    pub fn send_mpv_command(&self, name: String, args: Vec<String>) {
-        let widget = self.imp();
-
-        let args = args.iter().map(String::as_ref).collect_vec();
-        widget.send_command(&name, &args);
+    pub fn connect_playback_event<T: Fn(&str, Value) + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "playback-event",
+            false,
+            closure_local!(move |_: Video, name: &str, data: &str| {
+                match serde_json::from_str::<Value>(data) {
+                    Ok(data) => callback(name, data),
+                    Err(e) => warn!("Failed to parse event {name}: {e}"),
+                }
+            }),
+        );
     }
 
-    pub fn observe_mpv_property(&self, name: String) {
+    /// Loads the user's mpv.conf, input.conf and scripts from the shell config dir.
+    ///
+    /// This runs after mpv was initialized, so options only read at startup
+    /// (`vo`, `gpu-api`, `config-dir`, ...) have no effect and the shell's own
+    /// init options always win.
+    pub fn load_user_config(&self) {
+        let dir = user_config_dir();
         let widget = self.imp();
+        info!("Loading mpv config from {}", dir.display());
 
-        match name.as_str() {
-            name if FLOAT_PROPERTIES.contains(&name) => {
-                widget.observe_property(name, Format::Double);
-            }
-            name if BOOL_PROPERTIES.contains(&name) => {
-                widget.observe_property(name, Format::Flag);
-            }
-            name if STRING_PROPERTIES.contains(&name) => {
-                widget.observe_property(name, Format::String);
+        let load = |command: &str, path: &Path| {
+            let path = path.to_string_lossy();
+            if let Err(e) = widget.command(&[command, &path]) {
+                error!("Failed to load {path}: {e}");
             }
-            _ => warn!("Failed to observe property {name}: Unsupported"),
         };
+
+        let config_file = dir.join(CONFIG_FILE);
+        if config_file.is_file() {
+            load("load-config-file", &config_file);
+        }
+
+        let input_config_file = dir.join(INPUT_CONFIG_FILE);
+        if input_config_file.is_file() {
+            load("load-input-conf", &input_config_file);
+        }
+
+        if let Ok(entries) = fs::read_dir(dir.join(SCRIPTS_DIR)) {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .sorted()
+                .for_each(|script| load("load-script", &script));
+        }
     }
 
-    pub fn set_mpv_property(&self, name: String, value: Value) {
+    /// Saves the current frame into the pictures dir, resolving to the path of the file.
+    pub fn screenshot(
+        &self,
+        format: ScreenshotFormat,
+        subtitles: bool,
+    ) -> impl Future<Output = Result<PathBuf, String>> + 'static {
+        let mode = match subtitles {
+            true => "subtitles",
+            false => "video",
+        };
+
+        let request = screenshot_path(format).map(|path| {
+            let command = json!(["screenshot-to-file", path, mode]);
+            (path, self.imp().command_async(&command))
+        });
+
+        async move {
+            let (path, receiver) = request?;
+
+            receiver
+                .await
+                .unwrap_or_else(|_| Err("Command was dropped".to_owned()))?;
+
+            Ok(path)
+        }
+    }
+
+    /// Adds and selects an external subtitle file, resolving to mpv's reply.
+    pub fn add_subtitle(
+        &self,
+        path: &str,
+    ) -> impl Future<Output = Result<Value, String>> + 'static {
+        self.send_mpv_command(json!(["sub-add", path, "select"]))
+    }
+
+    pub fn connect_seeked<T: Fn() + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "seeked",
+            false,
+            closure_local!(move |_: Video| {
+                callback();
+            }),
+        );
+    }
+
+    /// Seeks by an offset in seconds, or to an absolute position.
+    pub fn seek(&self, position: f64, absolute: bool) {
+        let mode = match absolute {
+            true => "absolute",
+            false => "relative",
+        };
+
+        self.run_command(&["seek", &position.to_string(), mode]);
+    }
+
+    pub fn set_volume(&self, volume: f64) {
+        self.imp().set_property("volume", &json!(volume)).ok();
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.imp().set_property("speed", &json!(speed)).ok();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.imp().set_property("pause", &json!(paused)).ok();
+    }
+
+    pub fn toggle_pause(&self) {
+        self.run_command(&["cycle", "pause"]);
+    }
+
+    /// Unloads the current file, mpv goes idle and reports the playback as ended.
+    pub fn stop(&self) {
+        self.run_command(&["stop"]);
+    }
+
+    pub fn run_command(&self, args: &[&str]) {
+        if let Err(e) = self.imp().command(args) {
+            error!("Failed to run command {}: {e}", args.join(" "));
+        }
+    }
+
+    pub fn mpv_version(&self) -> Option<String> {
+        self.imp().get_property("mpv-version")
+    }
+
+    pub fn hwdec_interop(&self) -> Option<String> {
+        self.imp()
+            .get_property::<String>("hwdec-interop")
+            .filter(|interop| !interop.is_empty())
+    }
+
+    pub fn hwdec_current(&self) -> Option<String> {
+        self.imp()
+            .get_property::<String>("hwdec-current")
+            .filter(|current| !current.is_empty() && current != "no")
+    }
+
+    /// Runs an mpv command given as an array of arguments or a map of named arguments,
+    /// resolving to its result or the mpv error string.
+    pub fn send_mpv_command(
+        &self,
+        command: Value,
+    ) -> impl Future<Output = Result<Value, String>> + 'static {
+        let receiver = self.imp().command_async(&command);
+
+        async move {
+            receiver
+                .await
+                .unwrap_or_else(|_| Err("Command was dropped".to_owned()))
+        }
+    }
+
+    pub fn observe_mpv_property(&self, name: &str) -> Result<(), PropertyError> {
         let widget = self.imp();
 
-        match name.as_str() {
-            name if FLOAT_PROPERTIES.contains(&name) => {
-                if let Some(value) = value.as_f64() {
-                    widget.set_property(name, value);
-                }
-            }
-            name if BOOL_PROPERTIES.contains(&name) => {
-                if let Some(value) = value.as_bool() {
-                    widget.set_property(name, value);
+        if !widget.is_property_allowed(name) {
+            warn!("Failed to observe property {name}: Not allowed");
+            return Err(PropertyError::NotAllowed);
+        }
+
+        widget
+            .observe_property(name, WEB_OBSERVER)
+            .map_err(PropertyError::Mpv)
+    }
+
+    /// Whether a command sent by the web UI is allowlisted and respects the property policy.
+    ///
+    /// Prefix flags are not command names, so commands using them are rejected.
+    pub fn is_mpv_command_allowed(&self, command: &Value) -> bool {
+        match command {
+            Value::Array(args) => {
+                let Some((name, args)) = args.split_first() else {
+                    return false;
+                };
+
+                match name.as_str().unwrap_or_default() {
+                    name if !ALLOWED_COMMANDS.contains(&name) => false,
+                    "loadfile" => args.len() <= LOADFILE_MAX_ARGS,
+                    name if PROPERTY_COMMANDS.contains(&name) => args
+                        .first()
+                        .and_then(Value::as_str)
+                        .is_some_and(|property| self.imp().is_property_allowed(property)),
+                    _ => true,
                 }
             }
-            name if STRING_PROPERTIES.contains(&name) => {
-                if let Some(value) = value.as_str() {
-                    widget.set_property(name, value);
-                }
+            // Named arguments clash with the property name of `set`, so those need the array form
+            Value::Object(args) => {
+                let name = args.get("name").and_then(Value::as_str).unwrap_or_default();
+
+                ALLOWED_COMMANDS.contains(&name)
+                    && !PROPERTY_COMMANDS.contains(&name)
+                    && !args.contains_key("_flags")
+                    && !args.contains_key("options")
             }
-            name => warn!("Failed to set property {name}: Unsupported"),
-        };
+            _ => false,
+        }
     }
+
+    pub fn set_mpv_property(&self, name: &str, value: &Value) -> Result<(), PropertyError> {
+        let widget = self.imp();
+
+        if !widget.is_property_allowed(name) {
+            warn!("Failed to set property {name}: Not allowed");
+            return Err(PropertyError::NotAllowed);
+        }
+
+        widget.set_property(name, value).map_err(PropertyError::Mpv)
+    }
+}
+
+/// Shell specific mpv config dir, inside the sandbox when running as a Flatpak.
+pub fn user_config_dir() -> PathBuf {
+    glib::user_config_dir().join(DATA_DIR).join(CONFIG_DIR)
+}
+
+fn screenshot_path(format: ScreenshotFormat) -> Result<PathBuf, String> {
+    let dir = dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .ok_or("Failed to get pictures dir")?
+        .join(SCREENSHOTS_DIR);
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create screenshots dir: {e}"))?;
+
+    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S%.3f");
+    let file_name = format!("{APP_NAME}_{timestamp}.{}", format.extension());
+
+    Ok(dir.join(file_name))
 }
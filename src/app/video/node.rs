@@ -0,0 +1,168 @@
+use std::{
+    ffi::{CStr, CString, c_char, c_int},
+    mem, ptr, slice,
+};
+
+use libmpv2_sys::{mpv_format, mpv_node, mpv_node_list};
+use serde_json::{Map, Number, Value};
+
+/// Converts a node owned by mpv into json.
+///
+/// # Safety
+///
+/// `node` and everything it points to must stay valid for the duration of the call.
+pub unsafe fn to_value(node: &mpv_node) -> Value {
+    unsafe {
+        match node.format {
+            mpv_format::String | mpv_format::OsdString => Value::String(to_string(node.u.string)),
+            mpv_format::Flag => Value::Bool(node.u.flag != 0),
+            mpv_format::Int64 => Value::from(node.u.int64),
+            mpv_format::Double => {
+                Number::from_f64(node.u.double_).map_or(Value::Null, Value::Number)
+            }
+            mpv_format::NodeArray => {
+                let (values, _) = list(node.u.list);
+                Value::Array(values.iter().map(|value| to_value(value)).collect())
+            }
+            mpv_format::NodeMap => {
+                let (values, keys) = list(node.u.list);
+                let map = keys
+                    .iter()
+                    .zip(values)
+                    .map(|(key, value)| (to_string(*key), to_value(value)))
+                    .collect::<Map<_, _>>();
+
+                Value::Object(map)
+            }
+            _ => Value::Null,
+        }
+    }
+}
+
+unsafe fn to_string(string: *const c_char) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+
+    unsafe { CStr::from_ptr(string) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+unsafe fn list<'a>(list: *const mpv_node_list) -> (&'a [mpv_node], &'a [*mut c_char]) {
+    let Some(list) = (unsafe { list.as_ref() }) else {
+        return (&[], &[]);
+    };
+
+    let len = list.num.max(0) as usize;
+    let values = match list.values.is_null() {
+        true => &[][..],
+        false => unsafe { slice::from_raw_parts(list.values, len) },
+    };
+    let keys = match list.keys.is_null() {
+        true => &[][..],
+        false => unsafe { slice::from_raw_parts(list.keys, len) },
+    };
+
+    (values, keys)
+}
+
+/// A node tree built from json, owning the memory it points to until mpv has copied it.
+pub struct OwnedNode {
+    node: mpv_node,
+    strings: Vec<CString>,
+    lists: Vec<Box<mpv_node_list>>,
+    values: Vec<Vec<mpv_node>>,
+    keys: Vec<Vec<*mut c_char>>,
+}
+
+impl OwnedNode {
+    pub fn new(value: &Value) -> Self {
+        let mut owned = Self {
+            node: unsafe { mem::zeroed() },
+            strings: Default::default(),
+            lists: Default::default(),
+            values: Default::default(),
+            keys: Default::default(),
+        };
+
+        owned.node = owned.build(value);
+        owned
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut mpv_node {
+        &mut self.node
+    }
+
+    fn build(&mut self, value: &Value) -> mpv_node {
+        let mut node: mpv_node = unsafe { mem::zeroed() };
+
+        match value {
+            Value::Null => node.format = mpv_format::None,
+            Value::Bool(flag) => {
+                node.format = mpv_format::Flag;
+                node.u.flag = *flag as c_int;
+            }
+            Value::Number(number) => match number.as_i64() {
+                Some(int) => {
+                    node.format = mpv_format::Int64;
+                    node.u.int64 = int;
+                }
+                None => {
+                    node.format = mpv_format::Double;
+                    node.u.double_ = number.as_f64().unwrap_or_default();
+                }
+            },
+            Value::String(string) => {
+                node.format = mpv_format::String;
+                node.u.string = self.string(string);
+            }
+            Value::Array(array) => {
+                let values = array.iter().map(|value| self.build(value)).collect();
+
+                node.format = mpv_format::NodeArray;
+                node.u.list = self.list(values, None);
+            }
+            Value::Object(map) => {
+                let keys = map.keys().map(|key| self.string(key)).collect();
+                let values = map.values().map(|value| self.build(value)).collect();
+
+                node.format = mpv_format::NodeMap;
+                node.u.list = self.list(values, Some(keys));
+            }
+        }
+
+        node
+    }
+
+    fn string(&mut self, string: &str) -> *mut c_char {
+        let string = CString::new(string).unwrap_or_default();
+        let pointer = string.as_ptr() as *mut c_char;
+
+        self.strings.push(string);
+        pointer
+    }
+
+    fn list(
+        &mut self,
+        mut values: Vec<mpv_node>,
+        keys: Option<Vec<*mut c_char>>,
+    ) -> *mut mpv_node_list {
+        let mut list = Box::new(mpv_node_list {
+            num: values.len() as c_int,
+            values: values.as_mut_ptr(),
+            keys: ptr::null_mut(),
+        });
+
+        if let Some(mut keys) = keys {
+            list.keys = keys.as_mut_ptr();
+            self.keys.push(keys);
+        }
+
+        self.values.push(values);
+
+        let pointer = &mut *list as *mut mpv_node_list;
+        self.lists.push(list);
+        pointer
+    }
+}
@@ -9,8 +9,8 @@ use gtk::{
 };
 use tracing::error;
 use webkit::{
-    NavigationPolicyDecision, PolicyDecisionType, UserContentInjectedFrames, UserScript,
-    UserScriptInjectionTime, prelude::WebViewExt,
+    NavigationPolicyDecision, NetworkSession, PolicyDecisionType, UserContentInjectedFrames,
+    UserScript, UserScriptInjectionTime, prelude::WebViewExt,
 };
 
 glib::wrapper! {
@@ -35,6 +35,12 @@ impl WebView {
         widget.webview.load_uri(uri);
     }
 
+    pub fn load_html(&self, html: &str) {
+        let widget = self.imp();
+
+        widget.webview.load_html(html, None);
+    }
+
     pub fn inject_script(&self, script: &'static str) {
         let widget = self.imp();
 
@@ -67,6 +73,12 @@ impl WebView {
         }
     }
 
+    pub fn network_session(&self) -> Option<NetworkSession> {
+        let widget = self.imp();
+
+        widget.webview.network_session()
+    }
+
     pub fn send(&self, message: &str) {
         let widget = self.imp();
 
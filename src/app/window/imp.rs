@@ -1,4 +1,12 @@
-use std::{cell::Cell, fs::File, os::fd::AsFd, sync::Arc};
+use std::{
+    cell::Cell,
+    fs::File,
+    os::fd::AsFd,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
@@ -13,14 +21,52 @@ use ashpd::{
     enumflags2::BitFlags,
 };
 use gtk::{
+    DropTarget, PropagationPhase,
+    gdk::{DragAction, FileList},
     gio::Settings,
-    glib::{self, clone, subclass::InitializingObject},
+    glib::{self, clone, subclass::InitializingObject, subclass::Signal},
     prelude::WidgetExt,
 };
 use tokio::sync::Mutex;
 use tracing::error;
 
-use crate::{app::config::APP_ID, spawn_local, utils::IS_DESKTOP_KDE};
+use crate::{
+    app::config::{APP_ID, MAGNET_SCHEME, MEDIA_EXTENSIONS, SUBTITLE_EXTENSIONS, URI_SCHEME},
+    spawn_local,
+    utils::{IS_DESKTOP_KDE, uri_extension},
+};
+
+/// Deeplinks, magnets, media and subtitle files, the same kinds of uris the shell can open.
+fn is_droppable(uri: &str) -> bool {
+    uri.starts_with(URI_SCHEME)
+        || uri.starts_with(MAGNET_SCHEME)
+        || uri_extension(uri).is_some_and(|extension| {
+            MEDIA_EXTENSIONS.contains(&extension.as_str())
+                || SUBTITLE_EXTENSIONS.contains(&extension.as_str())
+        })
+}
+
+fn droppable_uris(value: Option<&glib::Value>) -> Vec<String> {
+    value
+        .and_then(|value| value.get::<FileList>().ok())
+        .map(|files| {
+            files
+                .files()
+                .iter()
+                .map(|file| file.uri().to_string())
+                .filter(|uri| is_droppable(uri))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Only claims drags the shell can open, anything else is left to the web UI.
+fn drop_action(target: &DropTarget) -> DragAction {
+    match droppable_uris(target.value().as_ref()).is_empty() {
+        true => DragAction::empty(),
+        false => DragAction::COPY,
+    }
+}
 
 #[derive(Default, glib::Properties, gtk::CompositeTemplate)]
 #[properties(wrapper_type = super::Window)]
@@ -33,6 +79,7 @@ pub struct Window {
     #[template_child]
     pub overlay: TemplateChild<gtk::Overlay>,
     pub inhibit_request: Arc<Mutex<Option<Request<()>>>>,
+    pub inhibit_available: Arc<AtomicBool>,
 }
 
 impl Window {
@@ -55,6 +102,25 @@ impl Window {
         ));
     }
 
+    fn probe_inhibit(&self) {
+        let object = self.obj();
+        let inhibit_available = self.inhibit_available.clone();
+
+        spawn_local!(clone!(
+            #[weak]
+            object,
+            async move {
+                let available = InhibitProxy::new()
+                    .await
+                    .inspect_err(|e| error!("Inhibit portal is not available: {e}"))
+                    .is_ok();
+
+                inhibit_available.store(available, Ordering::SeqCst);
+                object.emit_by_name::<()>("inhibit-probed", &[]);
+            }
+        ));
+    }
+
     pub fn disable_idling(&self) {
         let object = self.obj();
         let inhibit_request = self.inhibit_request.clone();
@@ -149,6 +215,35 @@ impl Window {
         ));
     }
 
+    /// Catches drops before the webview so files and links end up in the player.
+    fn setup_drop_target(&self) {
+        // Uri lists, including magnet links dragged from a browser, arrive as a file list
+        let drop_target = DropTarget::new(FileList::static_type(), DragAction::COPY);
+        drop_target.set_preload(true);
+        drop_target.set_propagation_phase(PropagationPhase::Capture);
+
+        drop_target.connect_enter(|target, _, _| drop_action(target));
+        drop_target.connect_motion(|target, _, _| drop_action(target));
+
+        drop_target.connect_drop(clone!(
+            #[weak(rename_to = object)]
+            self.obj(),
+            #[upgrade_or]
+            false,
+            move |_, value, _, _| {
+                let uris = droppable_uris(Some(value));
+                if uris.is_empty() {
+                    return false;
+                }
+
+                object.emit_by_name::<()>("dropped", &[&uris]);
+                true
+            }
+        ));
+
+        self.overlay.add_controller(drop_target);
+    }
+
     pub fn show_header(&self, state: bool) {
         self.header.set_visible(self.decorations.get() && state);
     }
@@ -171,6 +266,18 @@ impl ObjectSubclass for Window {
 
 #[glib::derived_properties]
 impl ObjectImpl for Window {
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+        SIGNALS.get_or_init(|| {
+            vec![
+                Signal::builder("dropped")
+                    .param_types([Vec::<String>::static_type()])
+                    .build(),
+                Signal::builder("inhibit-probed").build(),
+            ]
+        })
+    }
+
     fn constructed(&self) {
         self.parent_constructed();
 
@@ -185,6 +292,9 @@ impl ObjectImpl for Window {
         if cfg!(debug_assertions) {
             self.obj().add_css_class("devel");
         }
+
+        self.probe_inhibit();
+        self.setup_drop_target();
     }
 }
 
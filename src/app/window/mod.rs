@@ -1,9 +1,11 @@
 mod imp;
 
+use std::sync::atomic::Ordering;
+
 use adw::subclass::prelude::*;
 use gtk::{
     Widget, gio,
-    glib::{self, object::IsA},
+    glib::{self, closure_local, object::IsA, prelude::ObjectExt},
     prelude::{GtkWindowExt, WidgetExt},
 };
 
@@ -28,6 +30,12 @@ impl Window {
         window.overlay.set_child(Some(widget));
     }
 
+    pub fn clear_underlay(&self) {
+        let window = self.imp();
+
+        window.overlay.set_child(None::<&Widget>);
+    }
+
     pub fn set_overlay(&self, widget: &impl IsA<Widget>) {
         let window = self.imp();
 
@@ -39,6 +47,27 @@ impl Window {
         self.set_fullscreened(fullscreen);
     }
 
+    pub fn connect_dropped<T: Fn(Vec<String>) + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "dropped",
+            false,
+            closure_local!(move |_: Window, uris: Vec<String>| {
+                callback(uris);
+            }),
+        );
+    }
+
+    /// Called once the availability of the inhibit portal is known.
+    pub fn connect_inhibit_probed<T: Fn() + 'static>(&self, callback: T) {
+        self.connect_closure(
+            "inhibit-probed",
+            false,
+            closure_local!(move |_: Window| {
+                callback();
+            }),
+        );
+    }
+
     pub fn connect_visibility<T: Fn(bool) + 'static>(&self, callback: T) {
         self.connect_visible_notify(move |window| {
             callback(window.is_visible());
@@ -49,6 +78,10 @@ impl Window {
         self.imp().request_backgound();
     }
 
+    pub fn can_inhibit(&self) -> bool {
+        self.imp().inhibit_available.load(Ordering::SeqCst)
+    }
+
     pub fn disable_idling(&self) {
         self.imp().disable_idling();
     }
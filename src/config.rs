@@ -1,8 +1,14 @@
+use std::time::Duration;
+
 pub const DATA_DIR: &str = "stremio";
 
 pub const GETTEXT_DOMAIN: &str = "stremio";
 pub const GETTEXT_DIR_DEV: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/po");
 pub const GETTEXT_DIR_FLATPAK: &str = "/app/share/locale";
 
-pub const STARTUP_URL: &str = "http://127.0.0.1:11470/proxy/d=https%3A%2F%2Fweb.stremio.com/";
+pub const SERVER_HOST: &str = "127.0.0.1";
+pub const SERVER_PORT: u16 = 11470;
+pub const SERVER_RUNTIME: &str = "node";
+pub const SERVER_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+pub const STARTUP_PATH: &str = "/proxy/d=https%3A%2F%2Fweb.stremio.com/";
 pub const IPC_KEY: &str = "LINUX";
@@ -3,16 +3,24 @@ mod config;
 mod server;
 mod utils;
 
-use std::{env, fs, ptr};
+use std::{env, fs, path::PathBuf, ptr, time::Duration};
 
 use clap::Parser;
-use gtk::glib::{ExitCode, object::ObjectExt};
+use gtk::{
+    gio::{Settings, prelude::SettingsExt},
+    glib::{ExitCode, object::ObjectExt},
+};
 use tokio::runtime::Runtime;
+use tracing::{error, info, warn};
+use url::Url;
 
 use crate::{
-    app::Application,
-    config::{DATA_DIR, GETTEXT_DIR_DEV, GETTEXT_DIR_FLATPAK, GETTEXT_DOMAIN, STARTUP_URL},
-    server::Server,
+    app::{APP_ID, Application},
+    config::{
+        DATA_DIR, GETTEXT_DIR_DEV, GETTEXT_DIR_FLATPAK, GETTEXT_DOMAIN, SERVER_HOST, SERVER_PORT,
+        SERVER_RUNTIME, SERVER_STOP_TIMEOUT, STARTUP_PATH,
+    },
+    server::{Server, ServerOptions},
 };
 
 #[derive(Parser, Debug)]
@@ -21,12 +29,33 @@ struct Args {
     /// Open dev tools
     #[arg(short, long)]
     dev: bool,
-    /// Startup url
-    #[arg(short, long, default_value = STARTUP_URL)]
-    url: String,
+    /// Startup url, defaults to the web app proxied by the streaming server
+    #[arg(short, long)]
+    url: Option<String>,
     /// Disable window decorations
     #[arg(short, long)]
     no_window_decorations: bool,
+    /// Skip loading the user mpv config and scripts
+    #[arg(long)]
+    safe_mode: bool,
+    /// Runtime used to run the streaming server
+    #[arg(long)]
+    server_runtime: Option<PathBuf>,
+    /// Path of the streaming server script
+    #[arg(long)]
+    server_path: Option<PathBuf>,
+    /// Extra environment variable for the streaming server, can be repeated
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_env)]
+    server_env: Vec<String>,
+    /// Port of the streaming server
+    #[arg(long)]
+    server_port: Option<u16>,
+    /// Seconds to wait for the streaming server to stop before killing it
+    #[arg(long, value_name = "SECONDS")]
+    server_stop_timeout: Option<u64>,
+    /// Use an already running streaming server instead of starting one
+    #[arg(long, value_name = "URL")]
+    external_server: Option<Url>,
 
     #[arg(trailing_var_arg = true)]
     trailing: Vec<String>,
@@ -61,16 +90,109 @@ fn main() -> ExitCode {
     });
 
     let args = Args::parse();
-
-    let mut server = Server::new();
-    server.start(args.dev).expect("Failed to start server");
+    let settings = Settings::new(APP_ID);
 
     let app = Application::new();
+
+    let server_url = match external_server(&args, &settings) {
+        Some(url) => {
+            info!("Using external server at {url}");
+            url.to_string()
+        }
+        None => {
+            let server_options = server_options(&args, &settings);
+            let url = format!("http://{SERVER_HOST}:{}", server_options.port);
+
+            app.set_server(Server::new(server_options));
+            url
+        }
+    };
+
+    let startup_url = args.url.unwrap_or_else(|| {
+        let server_url = server_url.trim_end_matches('/');
+        format!("{server_url}{STARTUP_PATH}")
+    });
+
     app.set_property("dev-mode", args.dev);
-    app.set_property("startup-url", args.url);
+    app.set_property("startup-url", startup_url);
+    app.set_property("server-url", server_url);
     app.set_property("decorations", !args.no_window_decorations);
+    app.set_property("safe-mode", args.safe_mode);
 
     let runtime = Runtime::new().expect("Failed to create Tokio runtime");
     let _guard = runtime.enter();
     app.run(args.trailing)
 }
+
+fn external_server(args: &Args, settings: &Settings) -> Option<Url> {
+    if let Some(ref url) = args.external_server {
+        return Some(url.clone());
+    }
+
+    let url = settings.string("external-server");
+    if url.is_empty() {
+        return None;
+    }
+
+    Url::parse(&url)
+        .inspect_err(|e| error!("Invalid external server url {url}: {e}"))
+        .ok()
+}
+
+fn server_options(args: &Args, settings: &Settings) -> ServerOptions {
+    let runtime = args.server_runtime.clone().unwrap_or_else(|| {
+        let runtime = settings.string("server-runtime");
+        match runtime.is_empty() {
+            true => PathBuf::from(SERVER_RUNTIME),
+            false => PathBuf::from(runtime.as_str()),
+        }
+    });
+
+    let file = args
+        .server_path
+        .clone()
+        .or_else(|| {
+            let path = settings.string("server-path");
+            (!path.is_empty()).then(|| PathBuf::from(path.as_str()))
+        })
+        .or_else(|| env::var_os("SERVER_PATH").map(PathBuf::from));
+
+    let env = settings
+        .strv("server-env")
+        .iter()
+        .filter_map(|variable| {
+            parse_env(variable)
+                .inspect_err(|e| warn!("Ignoring server-env setting entry: {e}"))
+                .ok()
+        })
+        .chain(args.server_env.iter().cloned())
+        .collect();
+
+    let port = args
+        .server_port
+        .or_else(|| u16::try_from(settings.int("server-port")).ok())
+        .unwrap_or(SERVER_PORT);
+
+    let stop_timeout = args
+        .server_stop_timeout
+        .or_else(|| u64::try_from(settings.int("server-stop-timeout")).ok())
+        .map_or(SERVER_STOP_TIMEOUT, Duration::from_secs);
+
+    ServerOptions {
+        runtime,
+        file,
+        env,
+        port,
+        stop_timeout,
+        dev: args.dev,
+    }
+}
+
+fn parse_env(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((key, _)) if !key.is_empty() => Ok(value.to_owned()),
+        _ => Err(format!(
+            "Invalid environment variable {value}, expected KEY=VALUE"
+        )),
+    }
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+pub const SUPERVISOR_INTERVAL: Duration = Duration::from_millis(500);
+pub const RESTART_DELAY: Duration = Duration::from_secs(1);
+pub const RESTART_DELAY_MAX: Duration = Duration::from_secs(30);
+pub const RESTART_LIMIT: u32 = 5;
+pub const RESTART_RESET_AFTER: Duration = Duration::from_secs(60);
+pub const STOP_INTERVAL: Duration = Duration::from_millis(100);
+pub const READY_INTERVAL: Duration = Duration::from_millis(250);
+pub const READY_TIMEOUT: Duration = Duration::from_secs(30);
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+pub const LOG_FILE: &str = "server.log";
+pub const LOG_MAX_SIZE: u64 = 1024 * 1024;
+pub const LOG_MAX_FILES: u32 = 3;
@@ -0,0 +1,140 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use tracing::{Level, debug, error, info, trace, warn};
+
+use crate::config::DATA_DIR;
+
+use super::config::{LOG_FILE, LOG_MAX_FILES, LOG_MAX_SIZE};
+
+pub fn log_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(DATA_DIR).join(LOG_FILE))
+}
+
+pub fn read_log(max_size: u64) -> String {
+    let Some(mut file) = log_path().and_then(|path| File::open(path).ok()) else {
+        return String::new();
+    };
+
+    let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    file.seek(SeekFrom::Start(size.saturating_sub(max_size)))
+        .ok();
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).ok();
+
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+pub struct ServerLog {
+    path: Option<PathBuf>,
+    file: Option<File>,
+    size: u64,
+}
+
+impl ServerLog {
+    pub fn new() -> Self {
+        let path = log_path();
+        let file = path.as_deref().and_then(open);
+        let size = file
+            .as_ref()
+            .and_then(|file| file.metadata().ok())
+            .map_or(0, |metadata| metadata.len());
+
+        Self { path, file, size }
+    }
+
+    pub fn start(&mut self, file: &Path) {
+        let timestamp = Local::now().to_rfc3339();
+        self.append(&format!("--- {timestamp} Starting {} ---", file.display()));
+    }
+
+    pub fn write(&mut self, line: &str, stderr: bool) {
+        let level = parse_level(line).unwrap_or(match stderr {
+            true => Level::WARN,
+            false => Level::INFO,
+        });
+
+        match level {
+            Level::ERROR => error!(target: "server", "{}", line),
+            Level::WARN => warn!(target: "server", "{}", line),
+            Level::INFO => info!(target: "server", "{}", line),
+            Level::DEBUG => debug!(target: "server", "{}", line),
+            _ => trace!(target: "server", "{}", line),
+        }
+
+        self.append(line);
+    }
+
+    fn append(&mut self, line: &str) {
+        let length = line.len() as u64 + 1;
+        if self.size + length > LOG_MAX_SIZE {
+            self.rotate();
+        }
+
+        if let Some(ref mut file) = self.file
+            && writeln!(file, "{line}").is_ok()
+        {
+            self.size += length;
+        }
+    }
+
+    fn rotate(&mut self) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+
+        self.file = None;
+
+        for index in (1..LOG_MAX_FILES).rev() {
+            let from = rotated_path(path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(path, index + 1)).ok();
+            }
+        }
+
+        if let Err(e) = fs::rename(path, rotated_path(path, 1)) {
+            error!("Failed to rotate server log: {e}");
+        }
+
+        self.file = open(path);
+        self.size = 0;
+    }
+}
+
+/// Reads a leading level such as `ERROR:`, `[warn]` or `info ` from a server line.
+fn parse_level(line: &str) -> Option<Level> {
+    let line = line.trim_start().trim_start_matches('[');
+    let end = line.find(|c: char| !c.is_ascii_alphabetic())?;
+    if !line[end..].starts_with([']', ':', ' ']) {
+        return None;
+    }
+
+    match line[..end].to_ascii_lowercase().as_str() {
+        "error" | "err" | "fatal" => Some(Level::ERROR),
+        "warn" | "warning" => Some(Level::WARN),
+        "info" => Some(Level::INFO),
+        "debug" => Some(Level::DEBUG),
+        "trace" | "verbose" => Some(Level::TRACE),
+        _ => None,
+    }
+}
+
+fn open(path: &Path) -> Option<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .inspect_err(|e| error!("Failed to open server log: {e}"))
+        .ok()
+}
+
+fn rotated_path(path: &Path, index: u32) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{index}"));
+    PathBuf::from(path)
+}
@@ -0,0 +1,372 @@
+mod config;
+mod log;
+
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process::{self, Child, Command},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, anyhow};
+use flume::{Receiver, Sender, unbounded};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time,
+};
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::config::IPC_KEY;
+
+pub use log::{log_path, read_log};
+
+use log::ServerLog;
+
+use config::{
+    PROBE_TIMEOUT, READY_INTERVAL, READY_TIMEOUT, RESTART_DELAY, RESTART_DELAY_MAX, RESTART_LIMIT,
+    RESTART_RESET_AFTER, STOP_INTERVAL, SUPERVISOR_INTERVAL,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ServerEvent {
+    Started,
+    Restarting(u32),
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    pub runtime: PathBuf,
+    pub file: Option<PathBuf>,
+    pub env: Vec<String>,
+    pub port: u16,
+    pub stop_timeout: Duration,
+    pub dev: bool,
+}
+
+pub struct Server {
+    process: Arc<Mutex<Option<Child>>>,
+    stopping: Arc<AtomicBool>,
+    options: ServerOptions,
+    log: Arc<Mutex<ServerLog>>,
+    sender: Sender<ServerEvent>,
+    receiver: Receiver<ServerEvent>,
+}
+
+impl Server {
+    pub fn new(options: ServerOptions) -> Self {
+        let (sender, receiver) = unbounded::<ServerEvent>();
+
+        Self {
+            process: Default::default(),
+            stopping: Default::default(),
+            options,
+            log: Arc::new(Mutex::new(ServerLog::new())),
+            sender,
+            receiver,
+        }
+    }
+
+    pub fn events(&self) -> Receiver<ServerEvent> {
+        self.receiver.clone()
+    }
+
+    pub fn start(&self) -> anyhow::Result<()> {
+        let child = spawn(&self.options, &self.log)?;
+
+        self.stopping.store(false, Ordering::SeqCst);
+        if let Ok(mut process) = self.process.lock() {
+            *process = Some(child);
+        }
+
+        self.supervise();
+
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.process.lock().is_ok_and(|process| process.is_some())
+    }
+
+    pub fn stop(&self) -> anyhow::Result<()> {
+        self.stopping.store(true, Ordering::SeqCst);
+
+        let process = self
+            .process
+            .lock()
+            .map_err(|_| anyhow!("Failed to lock server process"))?
+            .take();
+
+        let Some(mut process) = process else {
+            return Ok(());
+        };
+
+        if let Some(status) = process
+            .try_wait()
+            .context("Failed to check server process")?
+        {
+            info!("Server already exited with {status}");
+            return Ok(());
+        }
+
+        let group = process.id() as libc::pid_t;
+        signal(group, libc::SIGTERM)?;
+
+        let deadline = Instant::now() + self.options.stop_timeout;
+        while Instant::now() < deadline {
+            if let Some(status) = process
+                .try_wait()
+                .context("Failed to check server process")?
+            {
+                info!("Server stopped with {status}");
+                return Ok(());
+            }
+
+            thread::sleep(STOP_INTERVAL);
+        }
+
+        warn!(
+            "Server did not stop within {:?}, killing it",
+            self.options.stop_timeout
+        );
+        signal(group, libc::SIGKILL)?;
+
+        let status = process
+            .wait()
+            .context("Failed to wait for server process")?;
+        info!("Server killed with {status}");
+
+        Ok(())
+    }
+
+    fn supervise(&self) {
+        let options = self.options.clone();
+        let log = self.log.clone();
+        let process = self.process.clone();
+        let stopping = self.stopping.clone();
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            let mut attempt = 0;
+            let mut started_at = Instant::now();
+
+            loop {
+                thread::sleep(SUPERVISOR_INTERVAL);
+
+                if stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let status = match process.lock() {
+                    Ok(mut process) => match process.as_mut().map(Child::try_wait) {
+                        Some(Ok(Some(status))) => status,
+                        Some(Ok(None)) => continue,
+                        Some(Err(e)) => {
+                            error!("Failed to check server process: {e}");
+                            continue;
+                        }
+                        None => break,
+                    },
+                    Err(_) => break,
+                };
+
+                if started_at.elapsed() > RESTART_RESET_AFTER {
+                    attempt = 0;
+                }
+
+                attempt += 1;
+
+                if attempt > RESTART_LIMIT {
+                    error!("Server exited with {status}, giving up after {RESTART_LIMIT} restarts");
+
+                    if let Ok(mut process) = process.lock() {
+                        *process = None;
+                    }
+
+                    sender.send(ServerEvent::Failed).ok();
+                    break;
+                }
+
+                let delay = RESTART_DELAY
+                    .saturating_mul(2u32.pow(attempt - 1))
+                    .min(RESTART_DELAY_MAX);
+
+                warn!("Server exited with {status}, restarting in {delay:?} (attempt {attempt})");
+                sender.send(ServerEvent::Restarting(attempt)).ok();
+
+                thread::sleep(delay);
+
+                let Ok(mut process) = process.lock() else {
+                    break;
+                };
+
+                if stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                started_at = Instant::now();
+
+                match spawn(&options, &log) {
+                    Ok(child) => {
+                        info!("Server restarted");
+
+                        *process = Some(child);
+                        sender.send(ServerEvent::Started).ok();
+                    }
+                    Err(e) => error!("Failed to restart server: {e}"),
+                }
+            }
+        });
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            error!("Failed to stop server: {e:#}");
+        }
+    }
+}
+
+fn signal(group: libc::pid_t, signal: libc::c_int) -> anyhow::Result<()> {
+    if unsafe { libc::kill(-group, signal) } == -1 {
+        let error = io::Error::last_os_error();
+
+        if error.raw_os_error() != Some(libc::ESRCH) {
+            return Err(error).context("Failed to signal server process group");
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn wait_until_ready(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        error!("Invalid server url {url}");
+        return false;
+    };
+
+    // Each probe has its own deadline, a server that accepts but never answers is retried
+    let probe = async {
+        while !time::timeout(PROBE_TIMEOUT, is_ready(&url))
+            .await
+            .unwrap_or(false)
+        {
+            time::sleep(READY_INTERVAL).await;
+        }
+    };
+
+    time::timeout(READY_TIMEOUT, probe).await.is_ok()
+}
+
+async fn is_ready(url: &Url) -> bool {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+
+    let address = format!("{host}:{port}");
+    let Ok(mut stream) = TcpStream::connect(&address).await else {
+        return false;
+    };
+
+    if url.scheme() != "http" {
+        return true;
+    }
+
+    let request = format!("HEAD {} HTTP/1.0\r\nHost: {address}\r\n\r\n", url.path());
+    if stream.write_all(request.as_bytes()).await.is_err() {
+        return false;
+    }
+
+    let mut status = [0; 5];
+    stream.read_exact(&mut status).await.is_ok() && &status == b"HTTP/"
+}
+
+fn spawn(options: &ServerOptions, log: &Arc<Mutex<ServerLog>>) -> anyhow::Result<Child> {
+    let file = options.file.as_ref().ok_or_else(|| {
+        anyhow!("Streaming server path is not set, use --server-path or the server-path setting")
+    })?;
+
+    if !file.is_file() {
+        return Err(anyhow!("Streaming server not found at {}", file.display()));
+    }
+
+    let env = options
+        .env
+        .iter()
+        .filter_map(|variable| variable.split_once('='));
+
+    let mut command = Command::new(&options.runtime);
+    command
+        .env("NO_CORS", (options.dev as i32).to_string())
+        .env("SERVER_IPC_KEY", IPC_KEY)
+        .env("HTTP_PORT", options.port.to_string())
+        .envs(env)
+        .arg(file.as_os_str())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .process_group(0);
+
+    unsafe {
+        command.pre_exec(move || {
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM);
+            Ok(())
+        });
+    }
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", options.runtime.display()))?;
+
+    if let Ok(mut log) = log.lock() {
+        log.start(file);
+    }
+
+    if let Some(stdout) = child.stdout.take() {
+        capture_output(stdout, log.clone(), false);
+    }
+
+    if let Some(stderr) = child.stderr.take() {
+        capture_output(stderr, log.clone(), true);
+    }
+
+    Ok(child)
+}
+
+fn capture_output<T: Read + Send + 'static>(output: T, log: Arc<Mutex<ServerLog>>, stderr: bool) {
+    let mut reader = BufReader::new(output);
+
+    // The pipe must be drained until EOF, otherwise the server blocks once it fills
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+
+        loop {
+            buffer.clear();
+
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buffer);
+                    let line = line.trim_end_matches(['\r', '\n']);
+
+                    if let Ok(mut log) = log.lock() {
+                        log.write(line, stderr);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    error!("Failed to read server output: {e}");
+                    break;
+                }
+            }
+        }
+    });
+}
@@ -1,11 +1,13 @@
 use std::{
     io::{self, Cursor},
+    path::Path,
     sync::LazyLock,
 };
 
 use anyhow::{Context, Error, Result, anyhow};
 use base64::{engine::general_purpose, read};
 use tokio::fs;
+use url::Url;
 
 #[macro_export]
 macro_rules! spawn_local {
@@ -20,6 +22,16 @@ pub static IS_DESKTOP_KDE: LazyLock<bool> = LazyLock::new(|| {
         .is_some_and(|value| value == "KDE")
 });
 
+/// Lowercase extension of the path of a uri, its query and fragment are ignored.
+pub fn uri_extension(uri: &str) -> Option<String> {
+    let url = Url::parse(uri).ok()?;
+
+    Path::new(url.path())
+        .extension()?
+        .to_str()
+        .map(str::to_lowercase)
+}
+
 pub fn decode_base64(data: &str) -> Result<Vec<u8>> {
     let mut input = Cursor::new(data);
     let mut output = Vec::new();